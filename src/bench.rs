@@ -0,0 +1,219 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::input::Input;
+use crate::solution::{Part, Puzzle};
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting every allocation it makes. Install it
+/// with `#[global_allocator]` in the binary; without it, benchmarks report
+/// no allocations.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+fn allocations() -> (u64, u64) {
+    (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed))
+}
+
+/// The part of a day's work being timed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Parse,
+    Solve(Part),
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Phase::Parse => f.pad("parse"),
+            Phase::Solve(Part::One) => f.pad("part 1"),
+            Phase::Solve(Part::Two) => f.pad("part 2"),
+        }
+    }
+}
+
+/// How long one phase took over repeated runs, and what it allocated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timing {
+    pub day: u8,
+    pub phase: Phase,
+    pub iterations: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    /// Allocations per run, averaged over the runs.
+    pub allocations: u64,
+    /// Bytes allocated per run, averaged over the runs.
+    pub bytes: u64,
+}
+
+/// Run `f` `iterations` times, returning the timing and the last result.
+fn measure<T, F>(day: u8, phase: Phase, iterations: usize, mut f: F) -> (Timing, T)
+  where F: FnMut() -> T
+{
+    let iterations = iterations.max(1);
+    // Sized up front, so recording a duration doesn't count as an allocation.
+    let mut times = Vec::with_capacity(iterations);
+    let mut result = None;
+    let (allocations_before, bytes_before) = allocations();
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let r = black_box(f());
+        times.push(start.elapsed());
+        result = Some(r);
+    }
+    let (allocations_after, bytes_after) = allocations();
+    times.sort();
+    let n = iterations as u64;
+    let timing = Timing {
+        day,
+        phase,
+        iterations,
+        min: times[0],
+        median: times[iterations / 2],
+        max: times[iterations - 1],
+        allocations: (allocations_after - allocations_before) / n,
+        bytes: (bytes_after - bytes_before) / n,
+    };
+    (timing, result.expect("at least one iteration"))
+}
+
+/// Time parsing `input` and solving each part, `iterations` times apiece.
+pub fn bench(puzzle: &dyn Puzzle, input: &Input, iterations: usize) -> Result<Vec<Timing>, Error> {
+    let day = puzzle.day();
+    let (parse_timing, parsed) = measure(day, Phase::Parse, iterations, || puzzle.parse(input.text()));
+    let parsed = parsed?;
+    let mut timings = vec![parse_timing];
+    for part in [Part::One, Part::Two] {
+        let (timing, answer) = measure(day, Phase::Solve(part), iterations, || parsed.solve(part));
+        answer?;
+        timings.push(timing);
+    }
+    Ok(timings)
+}
+
+/// Lay the timings out as a table, one row per phase.
+pub fn table(timings: &[Timing]) -> String {
+    let mut out = format!(
+        "{:>3}  {:<6}  {:>12}  {:>12}  {:>12}  {:>8}  {:>10}\n",
+        "day", "phase", "min", "median", "max", "allocs", "bytes"
+    );
+    for t in timings {
+        out.push_str(&format!(
+            "{:>3}  {:<6}  {:>12}  {:>12}  {:>12}  {:>8}  {:>10}\n",
+            t.day,
+            t.phase,
+            format!("{:?}", t.min),
+            format!("{:?}", t.median),
+            format!("{:?}", t.max),
+            t.allocations,
+            t.bytes
+        ));
+    }
+    out
+}
+
+/// The timings as a JSON array, durations in nanoseconds, for tracking
+/// across commits.
+pub fn json(timings: &[Timing]) -> String {
+    let rows: Vec<String> = timings
+        .iter()
+        .map(|t| {
+            format!(
+                "{{\"day\":{},\"phase\":\"{}\",\"iterations\":{},\"min_ns\":{},\"median_ns\":{},\"max_ns\":{},\"allocations\":{},\"bytes\":{}}}",
+                t.day,
+                t.phase,
+                t.iterations,
+                t.min.as_nanos(),
+                t.median.as_nanos(),
+                t.max.as_nanos(),
+                t.allocations,
+                t.bytes
+            )
+        })
+        .collect();
+    format!("[{}]", rows.join(",\n "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    fn timing(phase: Phase) -> Timing {
+        Timing {
+            day: 6,
+            phase,
+            iterations: 3,
+            min: Duration::from_nanos(1500),
+            median: Duration::from_micros(2),
+            max: Duration::from_millis(1),
+            allocations: 4,
+            bytes: 128,
+        }
+    }
+
+    #[test]
+    fn bench_every_phase() {
+        let puzzle = solution::find(6).unwrap();
+        let input = Input::from("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
+        let timings = bench(puzzle.as_ref(), &input, 3).unwrap();
+        let phases: Vec<Phase> = timings.iter().map(|t| t.phase).collect();
+        assert_eq!(phases, vec![Phase::Parse, Phase::Solve(Part::One), Phase::Solve(Part::Two)]);
+        for t in &timings {
+            assert_eq!(t.iterations, 3);
+            assert!(t.min <= t.median && t.median <= t.max);
+        }
+    }
+
+    #[test]
+    fn bench_reports_errors() {
+        let puzzle = solution::find(4).unwrap();
+        assert!(bench(puzzle.as_ref(), &Input::from("1-2"), 2).is_err());
+    }
+
+    #[test]
+    fn measure_sorts_times() {
+        let mut calls = 0;
+        let (t, last) = measure(1, Phase::Parse, 5, || { calls += 1; calls });
+        assert_eq!(last, 5);
+        assert_eq!(t.iterations, 5);
+        assert!(t.min <= t.median && t.median <= t.max);
+    }
+
+    #[test]
+    fn render_table() {
+        let table = table(&[timing(Phase::Parse)]);
+        assert_eq!(table.lines().nth(1), Some("  6  parse          1.5µs           2µs           1ms         4         128"));
+    }
+
+    #[test]
+    fn render_json() {
+        let json = json(&[timing(Phase::Parse), timing(Phase::Solve(Part::Two))]);
+        assert_eq!(json, "[{\"day\":6,\"phase\":\"parse\",\"iterations\":3,\"min_ns\":1500,\"median_ns\":2000,\"max_ns\":1000000,\"allocations\":4,\"bytes\":128},
+ {\"day\":6,\"phase\":\"part 2\",\"iterations\":3,\"min_ns\":1500,\"median_ns\":2000,\"max_ns\":1000000,\"allocations\":4,\"bytes\":128}]");
+    }
+}
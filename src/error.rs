@@ -0,0 +1,141 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The ways a line of puzzle input can be rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The text should have been a number.
+    InvalidNumber(String),
+    /// A separator the format requires is absent.
+    MissingDelimiter(char),
+    /// The line ends before the named part of it.
+    MissingField(&'static str),
+    /// A character the format doesn't allow, at a 1-based column.
+    UnexpectedChar { column: usize, found: char },
+    /// A rucksack has no item in every compartment.
+    NoCommonItem,
+    /// A rucksack has several items in every compartment.
+    MultipleCommonItems,
+    /// A rucksack's items can't be shared equally between its compartments.
+    UnevenCompartments { items: usize, compartments: usize },
+    /// The input ends part way through a group of elves.
+    IncompleteGroup { size: usize, found: usize },
+    /// No item is carried by every elf in a group.
+    NoGroupItem,
+    /// Several items are carried by every elf in a group.
+    MultipleGroupItems,
+    /// The text should have been a puzzle part, 1 or 2.
+    InvalidPart(String),
+    /// A datastream never has `width` different characters in a row.
+    NoMarker { width: usize },
+    /// A move names a stack that isn't in the drawing.
+    NoSuchStack(usize),
+    /// A range whose end comes before its start.
+    ReversedRange { first: i32, last: i32 },
+    /// Two stacks share a label.
+    DuplicateLabel(usize),
+    /// A move takes more crates than its stack holds at that point.
+    NotEnoughCrates { stack: usize, wanted: usize, available: usize },
+}
+
+impl ErrorKind {
+    /// Attach the position of the offending line.
+    pub fn at(self, line: usize, text: &str) -> Error {
+        Error { line, text: text.to_string(), kind: self }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::InvalidNumber(text) => write!(f, "`{text}` is not a number"),
+            ErrorKind::MissingDelimiter(c) => write!(f, "missing `{c}`"),
+            ErrorKind::MissingField(field) => write!(f, "missing {field}"),
+            ErrorKind::UnexpectedChar { column, found } => write!(f, "unexpected {found:?} at column {column}"),
+            ErrorKind::NoCommonItem => write!(f, "no item is in every compartment"),
+            ErrorKind::MultipleCommonItems => write!(f, "more than one item is in every compartment"),
+            ErrorKind::UnevenCompartments { items, compartments } => {
+                write!(f, "{items} items can't be split into {compartments} equal compartments")
+            },
+            ErrorKind::IncompleteGroup { size, found } => write!(f, "group has {found} of {size} elves"),
+            ErrorKind::NoGroupItem => write!(f, "no item is carried by every elf in the group"),
+            ErrorKind::MultipleGroupItems => write!(f, "more than one item is carried by every elf in the group"),
+            ErrorKind::InvalidPart(text) => write!(f, "`{text}` is not a part"),
+            ErrorKind::NoMarker { width } => write!(f, "no {width} characters in a row are all different"),
+            ErrorKind::NoSuchStack(stack) => write!(f, "there is no stack {stack}"),
+            ErrorKind::ReversedRange { first, last } => write!(f, "range {first}-{last} ends before it starts"),
+            ErrorKind::DuplicateLabel(label) => write!(f, "more than one stack is labelled {label}"),
+            ErrorKind::NotEnoughCrates { stack, wanted, available } => {
+                write!(f, "stack {stack} has {available} crates, not {wanted}")
+            },
+        }
+    }
+}
+
+/// A line of input that a puzzle rejected, and why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// 1-based line number within the input.
+    pub line: usize,
+    /// The offending line, as read.
+    pub text: String,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {} in {:?}", self.line, self.kind, self.text)
+    }
+}
+
+impl error::Error for Error {}
+
+/// Parse every line, reporting the first failure with its position.
+pub fn parse_lines<T, I>(lines: I) -> Result<Vec<T>, Error>
+  where T: FromStr<Err = ErrorKind>,
+        I: Iterator<Item = String>
+{
+    lines
+        .enumerate()
+        .map(|(i, line)| T::from_str(&line).map_err(|kind| kind.at(i + 1, &line)))
+        .collect()
+}
+
+/// Parse a number, keeping the text on failure.
+pub fn number<T: FromStr>(s: &str) -> Result<T, ErrorKind> {
+    T::from_str(s).map_err(|_| ErrorKind::InvalidNumber(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let e = ErrorKind::InvalidNumber("12x".to_string()).at(3, "12x");
+        assert_eq!(e.to_string(), "line 3: `12x` is not a number in \"12x\"");
+        let e = ErrorKind::UnexpectedChar { column: 2, found: '!' }.at(1, "a!");
+        assert_eq!(e.to_string(), "line 1: unexpected '!' at column 2 in \"a!\"");
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Small(u8);
+
+    impl FromStr for Small {
+        type Err = ErrorKind;
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(Small(number(s)?))
+        }
+    }
+
+    #[test]
+    fn parse_lines_reports_position() {
+        let lines = |v: Vec<&str>| v.into_iter().map(String::from).collect::<Vec<_>>().into_iter();
+        assert_eq!(parse_lines(lines(vec!["1", "2"])), Ok(vec![Small(1), Small(2)]));
+        assert_eq!(
+            parse_lines::<Small, _>(lines(vec!["1", "2", "x", "4"])),
+            Err(ErrorKind::InvalidNumber("x".to_string()).at(3, "x"))
+        );
+    }
+}
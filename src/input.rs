@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Puzzle input, read from wherever it lives. CRLF line endings become LF
+/// and trailing newlines are dropped, so every puzzle sees the same text
+/// however the file was saved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+    text: String,
+}
+
+impl Input {
+    /// Read a whole file. The error names the path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Input> {
+        let path = path.as_ref();
+        File::open(path)
+            .and_then(Input::from_reader)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+    }
+
+    /// Read everything piped to the process.
+    pub fn from_stdin() -> io::Result<Input> {
+        Input::from_reader(io::stdin().lock())
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Input> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(Input::from(text.as_str()))
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Each line, owned, ready for the puzzles' line-by-line runners.
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.text.lines().map(String::from)
+    }
+}
+
+/// The real puzzle input at `path`, for tests. Puzzle inputs aren't
+/// committed, so when the file is absent the test should skip itself rather
+/// than fail.
+#[cfg(test)]
+pub fn real_input(path: &str) -> Option<Input> {
+    if !Path::new(path).exists() {
+        eprintln!("skipping: {path} not found");
+        return None;
+    }
+    Some(Input::from_path(path).unwrap())
+}
+
+/// A width, size or count for a test. Panics on 0.
+#[cfg(test)]
+pub fn nonzero(n: usize) -> std::num::NonZeroUsize {
+    std::num::NonZeroUsize::new(n).unwrap()
+}
+
+/// Use in-memory text, such as an example embedded in a test.
+impl From<&str> for Input {
+    fn from(s: &str) -> Input {
+        let text = s.replace("\r\n", "\n").trim_end_matches('\n').to_string();
+        Input { text }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_line_endings() {
+        let input = Input::from("a\r\nb\r\n\r\nc\r\n\n\n");
+        assert_eq!(input.text(), "a\nb\n\nc");
+        assert_eq!(input.lines().collect::<Vec<_>>(), vec!["a", "b", "", "c"]);
+        assert_eq!(Input::from("").lines().count(), 0);
+    }
+
+    #[test]
+    fn from_reader() {
+        let input = Input::from_reader("1\n2\n".as_bytes()).unwrap();
+        assert_eq!(input, Input::from("1\n2"));
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let err = Input::from_path("inputs/no_such_puzzle.txt").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().starts_with("inputs/no_such_puzzle.txt: "));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod bench;
+pub mod error;
+pub mod input;
+pub mod puzzle_01;
+pub mod puzzle_02;
+pub mod puzzle_03;
+pub mod puzzle_04;
+pub mod puzzle_05;
+pub mod puzzle_06;
+pub mod solution;
+pub mod trace;
+pub mod verify;
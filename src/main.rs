@@ -1,10 +1,420 @@
-mod puzzle_01;
-mod puzzle_02;
-mod puzzle_03;
-mod puzzle_04;
-mod puzzle_05;
-mod puzzle_06;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use aoc::bench;
+use aoc::input::Input;
+use aoc::{puzzle_03, puzzle_05};
+use aoc::solution::{self, Part};
+use aoc::trace::{self, Level};
+use aoc::verify::{self, Answers, Status};
+
+const USAGE: &str = "usage:
+  aoc run --day <N> --part <1|2> [--input <path|->]
+  aoc explain --day 3 --part <1|2> [--input <path|->]
+  aoc verify [--answers <path>]
+  aoc bench [--day <N>] [--iterations <N>] [--json]
+  aoc visualize --part <1|2> [--input <path|->] [--delay <ms>] [--output <path>]
+Add -v (or set AOC_VERBOSE=1) to see each step on stderr, -vv for more.";
+
+/// Flags that take no value.
+const SWITCHES: &[&str] = &["--json"];
+
+#[global_allocator]
+static ALLOCATOR: bench::CountingAlloc = bench::CountingAlloc;
+
+/// What the user asked for on the command line.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run(Options),
+    /// Show how the answer is made up, for days that can.
+    Explain(Options),
+    Verify { answers: String },
+    Bench(BenchOptions),
+    Visualize(VisualizeOptions),
+}
+
+/// Which puzzle to run, and on what.
+#[derive(Debug, PartialEq)]
+struct Options {
+    day: u8,
+    part: Part,
+    /// The input file, or `-` for stdin. When absent, the puzzle's default
+    /// file under `inputs/` is used.
+    input: Option<String>,
+}
+
+/// Which puzzles to benchmark, and how.
+#[derive(Debug, PartialEq)]
+struct BenchOptions {
+    /// Only this day, rather than every day.
+    day: Option<u8>,
+    iterations: usize,
+    json: bool,
+}
+
+/// How to show day 5's crane at work.
+#[derive(Debug, PartialEq)]
+struct VisualizeOptions {
+    /// Part one's crane moves crates one at a time, part two's all at once.
+    part: Part,
+    input: Option<String>,
+    /// Pause between frames when animating.
+    delay: Duration,
+    /// Write every frame here instead of animating in the terminal.
+    output: Option<String>,
+}
+
+/// Pair up `--flag value` arguments. Switches get an empty value.
+fn flags<I>(mut args: I) -> Result<Vec<(String, String)>, String>
+  where I: Iterator<Item = String>
+{
+    let mut flags = Vec::new();
+    while let Some(flag) = args.next() {
+        let value = if SWITCHES.contains(&flag.as_str()) {
+            String::new()
+        } else {
+            args.next().ok_or(format!("missing value for `{flag}`"))?
+        };
+        flags.push((flag, value));
+    }
+    Ok(flags)
+}
+
+/// Pull the verbosity flags out of the arguments, wherever they are, and
+/// count them.
+fn split_verbosity(args: Vec<String>) -> (usize, Vec<String>) {
+    let mut count = 0;
+    let mut rest = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-v" | "--verbose" => count += 1,
+            "-vv" => count += 2,
+            _ => rest.push(arg),
+        }
+    }
+    (count, rest)
+}
+
+fn parse_day(value: &str) -> Result<u8, String> {
+    u8::from_str(value).map_err(|_| format!("bad day `{value}`"))
+}
+
+fn parse_args<I>(mut args: I) -> Result<Command, String>
+  where I: Iterator<Item = String>
+{
+    let command = args.next().ok_or("missing command")?;
+    let flags = flags(args)?;
+    match command.as_str() {
+        "run" => parse_run(flags).map(Command::Run),
+        "explain" => parse_run(flags).map(Command::Explain),
+        "verify" => {
+            let mut answers = verify::ANSWERS_FILE.to_string();
+            for (flag, value) in flags {
+                match flag.as_str() {
+                    "--answers" | "-a" => { answers = value; },
+                    _ => { return Err(format!("unknown flag `{flag}`")); },
+                }
+            }
+            Ok(Command::Verify { answers })
+        },
+        "bench" => parse_bench(flags).map(Command::Bench),
+        "visualize" => parse_visualize(flags).map(Command::Visualize),
+        other => Err(format!("unknown command `{other}`")),
+    }
+}
+
+fn parse_run(flags: Vec<(String, String)>) -> Result<Options, String> {
+    let mut day = None;
+    let mut part = None;
+    let mut input = None;
+    for (flag, value) in flags {
+        match flag.as_str() {
+            "--day" | "-d" => {
+                day = Some(parse_day(&value)?);
+            },
+            "--part" | "-p" => {
+                part = Some(Part::from_str(&value).map_err(|e| e.to_string())?);
+            },
+            "--input" | "-i" => {
+                input = Some(value);
+            },
+            _ => { return Err(format!("unknown flag `{flag}`")); },
+        }
+    }
+    let day = day.ok_or("missing --day")?;
+    let part = part.ok_or("missing --part")?;
+    Ok(Options { day, part, input })
+}
+
+fn parse_bench(flags: Vec<(String, String)>) -> Result<BenchOptions, String> {
+    let mut options = BenchOptions { day: None, iterations: 10, json: false };
+    for (flag, value) in flags {
+        match flag.as_str() {
+            "--day" | "-d" => {
+                options.day = Some(parse_day(&value)?);
+            },
+            "--iterations" | "-n" => {
+                options.iterations = usize::from_str(&value)
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or(format!("bad iteration count `{value}`"))?;
+            },
+            "--json" => {
+                options.json = true;
+            },
+            _ => { return Err(format!("unknown flag `{flag}`")); },
+        }
+    }
+    Ok(options)
+}
+
+fn parse_visualize(flags: Vec<(String, String)>) -> Result<VisualizeOptions, String> {
+    let mut part = None;
+    let mut options = VisualizeOptions { part: Part::One, input: None, delay: Duration::from_millis(200), output: None };
+    for (flag, value) in flags {
+        match flag.as_str() {
+            "--part" | "-p" => {
+                part = Some(Part::from_str(&value).map_err(|e| e.to_string())?);
+            },
+            "--input" | "-i" => {
+                options.input = Some(value);
+            },
+            "--delay" => {
+                let ms = u64::from_str(&value).map_err(|_| format!("bad delay `{value}`"))?;
+                options.delay = Duration::from_millis(ms);
+            },
+            "--output" | "-o" => {
+                options.output = Some(value);
+            },
+            _ => { return Err(format!("unknown flag `{flag}`")); },
+        }
+    }
+    options.part = part.ok_or("missing --part")?;
+    Ok(options)
+}
+
+/// Read the puzzle's input: the file given on the command line (`-` for
+/// stdin), or the puzzle's default.
+fn read_input(input: Option<&str>, default: &str) -> io::Result<Input> {
+    match input {
+        None => Input::from_path(default),
+        Some("-") => Input::from_stdin(),
+        Some(path) => Input::from_path(path),
+    }
+}
+
+/// Dispatch to the puzzle for the requested day and render its answer.
+fn solve(options: &Options) -> Result<String, String> {
+    let puzzle = solution::find(options.day).ok_or(format!("no solution for day {}", options.day))?;
+    let input = read_input(options.input.as_deref(), puzzle.input_file()).map_err(|e| e.to_string())?;
+    let parsed = puzzle.parse(input.text()).map_err(|e| e.to_string())?;
+    parsed.solve(options.part).map_err(|e| e.to_string())
+}
+
+/// Break the answer down into what each part of the input contributed.
+fn explain(options: &Options) -> Result<String, String> {
+    if options.day != 3 {
+        return Err(format!("no explanation for day {}", options.day));
+    }
+    let input = read_input(options.input.as_deref(), puzzle_03::INPUT_FILE).map_err(|e| e.to_string())?;
+    let shares = match options.part {
+        Part::One => puzzle_03::rucksack_shares(input.lines(), puzzle_03::COMPARTMENTS),
+        Part::Two => puzzle_03::group_shares(input.lines(), puzzle_03::GROUP_SIZE),
+    };
+    Ok(puzzle_03::explain(&shares.map_err(|e| e.to_string())?))
+}
+
+/// Check every day against the known answers, returning whether all the
+/// answers we know about were reproduced.
+fn verify_all(answers_path: &str) -> Result<bool, String> {
+    let answers = Input::from_path(answers_path).map_err(|e| e.to_string())?;
+    let answers = Answers::from_str(answers.text()).map_err(|e| format!("{answers_path}: {e}"))?;
+    let checks = verify::verify(&solution::registry(), &answers, |p| Input::from_path(p.input_file()));
+    print!("{}", verify::table(&checks));
+    Ok(checks.iter().all(|c| !matches!(c.status, Status::Fail { .. } | Status::Error(_))))
+}
+
+/// Benchmark the chosen days on their default inputs. Days without an
+/// input file are skipped.
+fn bench_all(options: &BenchOptions) -> Result<String, String> {
+    let puzzles = match options.day {
+        Some(day) => vec![solution::find(day).ok_or(format!("no solution for day {day}"))?],
+        None => solution::registry(),
+    };
+    let mut timings = Vec::new();
+    for puzzle in puzzles {
+        let input = match Input::from_path(puzzle.input_file()) {
+            Ok(input) => input,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && options.day.is_none() => {
+                eprintln!("skipping day {}: {e}", puzzle.day());
+                continue;
+            },
+            Err(e) => { return Err(e.to_string()); },
+        };
+        let day_timings = bench::bench(puzzle.as_ref(), &input, options.iterations)
+            .map_err(|e| format!("day {}: {e}", puzzle.day()))?;
+        timings.extend(day_timings);
+    }
+    Ok(if options.json { bench::json(&timings) + "\n" } else { bench::table(&timings) })
+}
+
+/// Draw day 5's stacks after every move, either animated in the terminal
+/// or all at once into a file.
+fn visualize(options: &VisualizeOptions) -> Result<(), String> {
+    let input = read_input(options.input.as_deref(), puzzle_05::INPUT_FILE).map_err(|e| e.to_string())?;
+    let problem = puzzle_05::Problem::from_str(input.text()).map_err(|e| e.to_string())?;
+    let frames = problem.frames(puzzle_05::crane(options.part)).map_err(|e| e.to_string())?;
+    if let Some(path) = &options.output {
+        return fs::write(path, frames.join("\n\n") + "\n").map_err(|e| format!("{path}: {e}"));
+    }
+    let mut stdout = io::stdout().lock();
+    for frame in frames {
+        // Clear the screen and go home before drawing the next frame.
+        writeln!(stdout, "\x1b[2J\x1b[H{frame}").and_then(|_| stdout.flush()).map_err(|e| e.to_string())?;
+        thread::sleep(options.delay);
+    }
+    Ok(())
+}
 
 fn main() {
-    println!("Hello, Advent of Code!");
+    let (verbosity, args) = split_verbosity(env::args().skip(1).collect());
+    trace::set_level(match verbosity {
+        0 => trace::from_env().unwrap_or(Level::Off),
+        n => trace::from_count(n),
+    });
+    let command = parse_args(args.into_iter()).unwrap_or_else(|e| {
+        eprintln!("{e}\n{USAGE}");
+        process::exit(2);
+    });
+    match command {
+        Command::Run(options) => match solve(&options) {
+            Ok(answer) => println!("{answer}"),
+            Err(e) => {
+                eprintln!("day {} part {}: {e}", options.day, options.part);
+                process::exit(1);
+            }
+        },
+        Command::Explain(options) => match explain(&options) {
+            Ok(explanation) => print!("{explanation}"),
+            Err(e) => {
+                eprintln!("day {} part {}: {e}", options.day, options.part);
+                process::exit(1);
+            }
+        },
+        Command::Bench(options) => match bench_all(&options) {
+            Ok(report) => print!("{report}"),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+        Command::Visualize(options) => {
+            if let Err(e) = visualize(&options) {
+                eprintln!("day 5 part {}: {e}", options.part);
+                process::exit(1);
+            }
+        },
+        Command::Verify { answers } => match verify_all(&answers) {
+            Ok(true) => {},
+            Ok(false) => process::exit(1),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> impl Iterator<Item = String> + '_ {
+        s.split_whitespace().map(|a| a.to_string())
+    }
+
+    #[test]
+    fn parse_run() {
+        assert_eq!(parse_args(args("run --day 5 --part 2")), Ok(Command::Run(Options { day: 5, part: Part::Two, input: None })));
+        assert_eq!(
+            parse_args(args("run -d 3 -p 1 -i a.txt")),
+            Ok(Command::Run(Options { day: 3, part: Part::One, input: Some("a.txt".to_string()) }))
+        );
+    }
+
+    #[test]
+    fn parse_explain() {
+        assert_eq!(parse_args(args("explain -d 3 -p 2")), Ok(Command::Explain(Options { day: 3, part: Part::Two, input: None })));
+        assert!(parse_args(args("explain --day 3")).is_err());
+        assert!(explain(&Options { day: 4, part: Part::One, input: None }).is_err());
+    }
+
+    #[test]
+    fn parse_verify() {
+        assert_eq!(parse_args(args("verify")), Ok(Command::Verify { answers: "answers.txt".to_string() }));
+        assert_eq!(parse_args(args("verify --answers a.txt")), Ok(Command::Verify { answers: "a.txt".to_string() }));
+        assert!(parse_args(args("verify --day 1")).is_err());
+    }
+
+    #[test]
+    fn verbosity_flags() {
+        let (count, rest) = split_verbosity(args("run -v --day 1 --part 2 --verbose").collect());
+        assert_eq!(count, 2);
+        assert_eq!(parse_args(rest.into_iter()), Ok(Command::Run(Options { day: 1, part: Part::Two, input: None })));
+        assert_eq!(split_verbosity(args("-vv bench").collect()), (2, vec!["bench".to_string()]));
+        assert_eq!(split_verbosity(args("verify").collect()).0, 0);
+    }
+
+    #[test]
+    fn parse_bench() {
+        assert_eq!(
+            parse_args(args("bench")),
+            Ok(Command::Bench(BenchOptions { day: None, iterations: 10, json: false }))
+        );
+        assert_eq!(
+            parse_args(args("bench --json -d 3 --iterations 50")),
+            Ok(Command::Bench(BenchOptions { day: Some(3), iterations: 50, json: true }))
+        );
+        assert!(parse_args(args("bench --iterations 0")).is_err());
+        assert!(parse_args(args("bench --json 1")).is_err());
+    }
+
+    #[test]
+    fn parse_visualize() {
+        assert_eq!(
+            parse_args(args("visualize --part 2")),
+            Ok(Command::Visualize(VisualizeOptions { part: Part::Two, input: None, delay: Duration::from_millis(200), output: None }))
+        );
+        assert_eq!(
+            parse_args(args("visualize -p 1 -i a.txt --delay 0 -o frames.txt")),
+            Ok(Command::Visualize(VisualizeOptions {
+                part: Part::One,
+                input: Some("a.txt".to_string()),
+                delay: Duration::ZERO,
+                output: Some("frames.txt".to_string()),
+            }))
+        );
+        assert!(parse_args(args("visualize")).is_err());
+        assert!(parse_args(args("visualize --part 1 --delay soon")).is_err());
+    }
+
+    #[test]
+    fn parse_bad_args() {
+        assert!(parse_args(args("")).is_err());
+        assert!(parse_args(args("walk --day 1 --part 1")).is_err());
+        assert!(parse_args(args("run --day 1")).is_err());
+        assert!(parse_args(args("run --day 1 --part 3")).is_err());
+        assert!(parse_args(args("run --day x --part 1")).is_err());
+        assert!(parse_args(args("run --day 1 --part")).is_err());
+    }
+
+    #[test]
+    fn unknown_day() {
+        assert!(solve(&Options { day: 26, part: Part::One, input: None }).is_err());
+    }
 }
@@ -1,27 +1,27 @@
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
 use std::str::FromStr;
-use std::path::Path;
 
-const input_file : &'static str = "inputs/puzzle_1.txt";
-const fake_input_file : &'static str = "inputs/puzzle_1_fake.txt";
+use crate::error::{self, Error, ErrorKind};
+use crate::solution::Solution;
+use crate::trace;
+
+pub const INPUT_FILE : &str = "inputs/puzzle_1.txt";
 
 /// Every line in the input must parse into one of these forms.
 #[derive(PartialEq, Clone, Eq, Debug)]
-enum Entry {
+pub enum Entry {
   Blank,
   Calories(i32),
 }
 
 impl FromStr for Entry {
-  type Err = &'static str;
-  fn from_str(s: &str) -> Result<Self, &'static str> {
+  type Err = ErrorKind;
+  fn from_str(s: &str) -> Result<Self, ErrorKind> {
     match s {
       "" => Ok(Entry::Blank),
-      _ => {
-        let n = i32::from_str(s).map_err(|_| "Could not parse to number")?;
-        Ok(Entry::Calories(n))
-      }
+      _ => Ok(Entry::Calories(error::number(s)?)),
     }
   }
 }
@@ -29,154 +29,289 @@ impl FromStr for Entry {
 /// As we traverse the inputs, we will update this state.
 #[derive(Debug)]
 struct State {
-  leader_id: i32,
-  leader_total: i32,
-  current_id: i32,
-  current_total: i32,
+  elves: Vec<Elf>,
+  current_items: Vec<i32>,
 }
 
 impl State {
 
   pub fn new() -> State {
-    State { leader_id: 0, leader_total: 0, current_id: 0, current_total: 0 }
+    State { elves: vec![], current_items: vec![] }
   }
 
   /// Update the state according to an entry.
   pub fn step(&mut self, entry: &Entry) {
     match entry {
       Entry::Blank => {
-        self.current_id = self.current_id + 1;
-        self.current_total = 0;
+        self.close_elf();
       },
       Entry::Calories(c) => {
-        self.current_total = self.current_total + c;
-        self.update_leader();
+        self.current_items.push(*c);
       }
     }
   }
 
-  /// Look at the current elf entry and, if that elf is
-  /// the leader, update the leader stats.
-  pub fn update_leader(&mut self) {
-    if self.current_total > self.leader_total {
-      self.leader_id = self.current_id;
-      self.leader_total = self.current_total;
+  /// The items so far belong to one elf. Runs of blank lines don't make
+  /// empty elves.
+  fn close_elf(&mut self) {
+    if !self.current_items.is_empty() {
+      let items = std::mem::take(&mut self.current_items);
+      let total = items.iter().sum();
+      self.elves.push(Elf { index: self.elves.len() + 1, items, total });
     }
   }
 
+  /// Close off the last elf.
+  pub fn finish(mut self) -> Ranking {
+    self.close_elf();
+    Ranking { elves: self.elves }
+  }
+
 }
 
-pub fn run<'a, I>(lines: I) -> Result<(i32, i32), &'static str>
-  where I: Iterator< Item = String >
-{
-  let mut state = State::new();
-  let mut state2 = State2::new();
-  for line in lines {
-    match Entry::from_str(&line) {
-      Ok(entry) => {
-        state.step(&entry);
-        state2.step(&entry);
-        println!("{:?}", state2);
-      },
-      Err(e) => {
-        return Err(e);
-      }
-    }
-  }
-  Ok((state.leader_total, state2.top_3_sum()))
+/// One elf's inventory. Elves are numbered from 1 in input order.
+#[derive(PartialEq, Clone, Eq, Debug)]
+pub struct Elf {
+  pub index: usize,
+  pub items: Vec<i32>,
+  pub total: i32,
 }
 
-pub fn file_lines(path: &str) -> Box<dyn Iterator<Item = String>> {
-  let file = File::open(path).unwrap();
-  let lines = io::BufReader::new(file).lines().into_iter().map(|l| l.unwrap());
-  Box::new(lines)
+impl fmt::Display for Elf {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let items: Vec<String> = self.items.iter().map(|c| c.to_string()).collect();
+    write!(f, "elf {} carries {} calories ({})", self.index, self.total, items.join(" + "))
+  }
 }
 
+/// Every elf, ready to be asked for the leaders.
 #[derive(Debug)]
-struct State2 {
-  previous_totals: Vec<i32>,
-  current_total: i32,
+pub struct Ranking {
+  elves: Vec<Elf>,
 }
 
-impl State2 {
-  pub fn new() -> State2 {
-    State2 { previous_totals: vec![], current_total: 0 }
+impl Ranking {
+  pub fn elves(&self) -> &[Elf] {
+    &self.elves
   }
 
-  pub fn step(&mut self, entry: &Entry) {
-    match entry {
-      Entry::Blank => {
-        self.previous_totals.push(self.current_total);
-        self.current_total = 0;
-      },
-      Entry::Calories(n) => {
-        self.current_total = self.current_total + n;
+  /// The `n` elves carrying the most, most first. Ties go to the earlier elf.
+  /// Only `n` elves are held at a time, rather than sorting everyone.
+  pub fn top(&self, n: usize) -> Vec<&Elf> {
+    let mut heap = BinaryHeap::with_capacity(n.min(self.elves.len()) + 1);
+    for (i, elf) in self.elves.iter().enumerate() {
+      heap.push(Reverse((elf.total, Reverse(i))));
+      if heap.len() > n {
+        heap.pop();
       }
     }
+    heap
+      .into_sorted_vec()
+      .into_iter()
+      .map(|Reverse((_, Reverse(i)))| &self.elves[i])
+      .collect()
   }
 
-  pub fn top_3_sum(self) -> i32 {
-    let mut totals = self.previous_totals;
-    totals.push(self.current_total);
-    totals.sort();
-    totals.reverse();
-    totals.get(0).unwrap_or(&0) + totals.get(1).unwrap_or(&0) + totals.get(2).unwrap_or(&0)
+  /// The combined calories of the `n` elves carrying the most.
+  pub fn top_sum(&self, n: usize) -> i32 {
+    self.top(n).iter().map(|elf| elf.total).sum()
+  }
+}
+
+/// The answers to both parts, naming the elves involved.
+#[derive(PartialEq, Clone, Eq, Debug)]
+pub struct Report {
+  /// The elf carrying the most, if there are any elves.
+  pub leader: Option<Elf>,
+  /// Up to three elves carrying the most, most first.
+  pub top_3: Vec<Elf>,
+}
+
+impl Report {
+  pub fn leader_total(&self) -> i32 {
+    self.leader.as_ref().map_or(0, |elf| elf.total)
+  }
+
+  pub fn top_3_total(&self) -> i32 {
+    self.top_3.iter().map(|elf| elf.total).sum()
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match &self.leader {
+      Some(elf) => writeln!(f, "Most calories: {elf}")?,
+      None => writeln!(f, "No elves")?,
+    }
+    writeln!(f, "Top {} carry {} calories:", self.top_3.len(), self.top_3_total())?;
+    for elf in &self.top_3 {
+      writeln!(f, "  {elf}")?;
+    }
+    Ok(())
+  }
+}
+
+pub fn ranking(entries: &[Entry]) -> Ranking {
+  let mut state = State::new();
+  for entry in entries {
+    state.step(entry);
+    trace!("{:?}", state);
+  }
+  state.finish()
+}
+
+pub fn report(entries: &[Entry]) -> Report {
+  let ranking = ranking(entries);
+  Report {
+    leader: ranking.top(1).into_iter().next().cloned(),
+    top_3: ranking.top(3).into_iter().cloned().collect(),
+  }
+}
+
+pub fn parse<I>(lines: I) -> Result<Vec<Entry>, Error>
+  where I: Iterator< Item = String >
+{
+  error::parse_lines(lines)
+}
+
+pub fn run<I>(lines: I) -> Result<Report, Error>
+  where I: Iterator< Item = String >
+{
+  Ok(report(&parse(lines)?))
+}
+
+pub struct Day;
+
+impl Solution for Day {
+  const DAY: u8 = 1;
+  const INPUT_FILE: &'static str = INPUT_FILE;
+  type Input = Vec<Entry>;
+  type Answer1 = i32;
+  type Answer2 = i32;
+
+  fn parse(input: &str) -> Result<Vec<Entry>, Error> {
+    parse(input.lines().map(String::from))
+  }
+
+  fn part_1(entries: &Vec<Entry>) -> Result<i32, Error> {
+    Ok(ranking(entries).top_sum(1))
+  }
+
+  fn part_2(entries: &Vec<Entry>) -> Result<i32, Error> {
+    Ok(ranking(entries).top_sum(3))
   }
 }
 
-/*
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::input::{real_input, Input};
+
+  const EXAMPLE: &str = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+
+  fn lines(v: Vec<&str>) -> impl Iterator<Item = String> + '_ {
+    v.into_iter().map(|s| s.to_string())
+  }
+
+  fn totals<I>(lines: I) -> Result<(i32, i32), Error>
+    where I: Iterator< Item = String >
+  {
+    run(lines).map(|report| (report.leader_total(), report.top_3_total()))
+  }
 
   #[test]
-  fn parse() {
+  fn parse_entries() {
     assert_eq!( Entry::from_str(""), Ok(Entry::Blank));
     assert_eq!( Entry::from_str("123"), Ok(Entry::Calories(123)));
-    assert!( Entry::from_str("123 ").is_err());
+    assert_eq!( Entry::from_str("123 "), Err(ErrorKind::InvalidNumber("123 ".to_string())));
   }
 
   #[test]
   fn trivial() {
-    let input = vec![];
-    assert_eq!(run(input.into_iter()), Ok((0,0)));
+    assert_eq!(totals(lines(vec![])), Ok((0,0)));
+    assert_eq!(run(lines(vec![])).unwrap().leader, None);
+    assert_eq!(totals(lines(vec!["", ""])), Ok((0,0)));
   }
 
   #[test]
   fn middle_parse_error() {
-    let input : Vec<String> = vec!["1", "", "blah", "2"].into_iter().map(|s| s.to_string()).collect();
-    assert!(run(input.into_iter()).is_err());
+    assert_eq!(
+      totals(lines(vec!["1", "", "blah", "2"])),
+      Err(ErrorKind::InvalidNumber("blah".to_string()).at(3, "blah"))
+    );
   }
 
   #[test]
   fn small() {
-    let input : Vec<String> = vec!["1", "2", "3", "", "2"].into_iter().map(|s| s.to_string()).collect();
-    assert_eq!(run(input.into_iter()), Ok((6,8)));
+    assert_eq!(totals(lines(vec!["1", "2", "3", "", "2"])), Ok((6,8)));
   }
 
   #[test]
   fn small_2() {
-    let input : Vec<String> = vec!["1", "2", "3", "", "8"].into_iter().map(|s| s.to_string()).collect();
-    assert_eq!(run(input.into_iter()), Ok((8,14)));
+    assert_eq!(totals(lines(vec!["1", "2", "3", "", "8"])), Ok((8,14)));
   }
 
   #[test]
   fn small_3() {
-    let input : Vec<String> = vec!["", "1", "2", "3", "", "8", ""].into_iter().map(|s| s.to_string()).collect();
-    assert_eq!(run(input.into_iter()), Ok((8, 14)));
+    assert_eq!(totals(lines(vec!["", "1", "2", "3", "", "8", ""])), Ok((8, 14)));
+  }
+
+  #[test]
+  fn trailing_blank_lines() {
+    assert_eq!(totals(lines(vec!["1", "", "2", "", "", ""])), Ok((2, 3)));
+  }
+
+  #[test]
+  fn example() {
+    assert_eq!(totals(Input::from(EXAMPLE).lines()), Ok((24000, 45000)));
+  }
+
+  #[test]
+  fn example_report() {
+    let report = run(Input::from(EXAMPLE).lines()).unwrap();
+    assert_eq!(report.leader, Some(Elf { index: 4, items: vec![7000, 8000, 9000], total: 24000 }));
+    assert_eq!(report.top_3.iter().map(|elf| elf.index).collect::<Vec<_>>(), vec![4, 3, 5]);
+    assert!(report.to_string().starts_with("Most calories: elf 4 carries 24000 calories (7000 + 8000 + 9000)\n"));
+  }
+
+  #[test]
+  fn elves_are_numbered_by_group() {
+    let entries = parse(lines(vec!["", "", "5", "", "", "6", "7"])).unwrap();
+    let ranking = ranking(&entries);
+    assert_eq!(ranking.elves().len(), 2);
+    assert_eq!(ranking.elves()[1], Elf { index: 2, items: vec![6, 7], total: 13 });
+  }
+
+  #[test]
+  fn top_n() {
+    let entries = parse(Input::from(EXAMPLE).lines()).unwrap();
+    let ranking = ranking(&entries);
+    assert!(ranking.top(0).is_empty());
+    assert_eq!(ranking.top_sum(1), 24000);
+    assert_eq!(ranking.top_sum(2), 35000);
+    assert_eq!(ranking.top(10).len(), 5);
+    assert_eq!(ranking.top(usize::MAX).len(), 5);
+    assert_eq!(ranking.top_sum(usize::MAX), ranking.top_sum(5));
+    assert_eq!(ranking.top_sum(10), 55000);
+  }
+
+  #[test]
+  fn ties_go_to_the_earlier_elf() {
+    let entries = parse(lines(vec!["3", "", "5", "", "5", "", "1"])).unwrap();
+    let ranking = ranking(&entries);
+    assert_eq!(ranking.top(3).iter().map(|elf| elf.index).collect::<Vec<_>>(), vec![2, 3, 1]);
   }
 
   #[test]
   fn from_fake_file() {
-    let input = file_lines(fake_input_file);
-    assert_eq!(run(input.into_iter()), Ok((100,113)));
+    let Some(input) = real_input("inputs/puzzle_1_fake.txt") else { return };
+    assert_eq!(totals(input.lines()), Ok((100, 113)));
   }
 
   #[test]
   fn real() {
-    let input = file_lines(input_file);
-    assert_eq!(run(input.into_iter()), Ok((67027, 197291)));
+    let Some(input) = real_input(INPUT_FILE) else { return };
+    assert_eq!(totals(input.lines()), Ok((67027, 197291)));
   }
 
 }
-*/
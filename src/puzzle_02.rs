@@ -1,28 +1,74 @@
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::str::FromStr;
-use std::path::Path;
 
-const input_file : &'static str = "inputs/puzzle_2.txt";
-const fake_input_file : &'static str = "inputs/puzzle_2_fake.txt";
+use crate::error::{self, Error, ErrorKind};
+use crate::solution::Solution;
+
+pub const INPUT_FILE : &str = "inputs/puzzle_2.txt";
 
 #[derive(PartialEq, Clone, Eq, Debug)]
-enum Play {
+pub enum Play {
   Rock,
   Paper,
   Scissors,
 }
 
+impl Play {
+  /// The play that beats this one.
+  pub fn beaten_by(&self) -> Play {
+    match self {
+      Play::Rock => Play::Paper,
+      Play::Paper => Play::Scissors,
+      Play::Scissors => Play::Rock,
+    }
+  }
+
+  /// The play that this one beats.
+  pub fn beats(&self) -> Play {
+    self.beaten_by().beaten_by()
+  }
+}
+
+/// The second column of the strategy guide, as written.
+#[derive(PartialEq, Clone, Copy, Eq, Debug)]
+pub enum Column {
+  X,
+  Y,
+  Z,
+}
+
+/// How to read the second column of the strategy guide.
+#[derive(PartialEq, Clone, Copy, Eq, Debug)]
+pub enum Strategy {
+  /// X, Y and Z are the Rock, Paper or Scissors to play.
+  Move,
+  /// X, Y and Z say to lose, draw or win.
+  Outcome,
+}
+
+impl Strategy {
+  pub fn my_play(&self, them: &Play, column: Column) -> Play {
+    match (self, column) {
+      (Strategy::Move, Column::X) => Play::Rock,
+      (Strategy::Move, Column::Y) => Play::Paper,
+      (Strategy::Move, Column::Z) => Play::Scissors,
+      (Strategy::Outcome, Column::X) => them.beats(),
+      (Strategy::Outcome, Column::Y) => them.clone(),
+      (Strategy::Outcome, Column::Z) => them.beaten_by(),
+    }
+  }
+}
+
 /// Every line in the input must parse into one of these forms.
 #[derive(PartialEq, Clone, Eq, Debug)]
-struct Entry {
+pub struct Entry {
   them: Play,
-  me: Play,
+  column: Column,
 }
 
 impl Entry {
-  pub fn score(&self) -> u32 {
-    let Entry{ them, me } = self;
+  pub fn score(&self, strategy: Strategy) -> u32 {
+    let them = &self.them;
+    let me = &strategy.my_play(them, self.column);
     let win_portion = match (me, them) {
       (Play::Rock,     Play::Scissors) => 6,
       (Play::Scissors, Play::Paper) => 6,
@@ -39,111 +85,180 @@ impl Entry {
   }
 }
 
+/// The error for a character that isn't the one expected at `column`.
+fn unexpected(column: usize, c: Option<char>, field: &'static str) -> ErrorKind {
+  match c {
+    Some(found) => ErrorKind::UnexpectedChar { column, found },
+    None => ErrorKind::MissingField(field),
+  }
+}
+
 impl FromStr for Entry {
-  type Err = &'static str;
-  fn from_str(s: &str) -> Result<Self, &'static str> {
+  type Err = ErrorKind;
+  fn from_str(s: &str) -> Result<Self, ErrorKind> {
     let mut chars = s.chars();
     let them_char = chars.next();
     let space = chars.next();
     let me_char = chars.next();
-    if space != Some(' ') {
-      return Err("Bar parse for space");
-    }
     let them = match them_char {
       Some('A') => Play::Rock,
       Some('B') => Play::Paper,
       Some('C') => Play::Scissors,
       _ => {
-        return Err("Bad parse for them");
+        return Err(unexpected(1, them_char, "opponent's play"));
       }
     };
-    let me = match (me_char, them.clone()) {
-      (Some('X'),Play::Rock) => Play::Scissors,
-      (Some('X'),Play::Paper) => Play::Rock,
-      (Some('X'),Play::Scissors) => Play::Paper,
-      (Some('Y'),Play::Rock) => Play::Rock,
-      (Some('Y'),Play::Paper) => Play::Paper,
-      (Some('Y'),Play::Scissors) => Play::Scissors,
-      (Some('Z'),Play::Rock) => Play::Paper,
-      (Some('Z'),Play::Paper) => Play::Scissors,
-      (Some('Z'),Play::Scissors) => Play::Rock,
+    if space != Some(' ') {
+      return Err(unexpected(2, space, "space"));
+    }
+    let column = match me_char {
+      Some('X') => Column::X,
+      Some('Y') => Column::Y,
+      Some('Z') => Column::Z,
       _ => {
-        return Err("Bad parse for me");
+        return Err(unexpected(3, me_char, "response"));
       }
     };
-    Ok(Entry {them, me})
+    if let Some(found) = chars.next() {
+      return Err(ErrorKind::UnexpectedChar { column: 4, found });
+    }
+    Ok(Entry {them, column})
   }
 }
 
 /// As we traverse the inputs, we will update this state.
 #[derive(Debug)]
 struct State {
+  strategy: Strategy,
   score: u32,
 }
 
 impl State {
 
-  pub fn new() -> State {
-    State { score: 0 }
+  pub fn new(strategy: Strategy) -> State {
+    State { strategy, score: 0 }
   }
 
   /// Update the state according to an entry.
   pub fn step(&mut self, entry: &Entry) {
-    self.score = self.score + entry.score();
+    self.score += entry.score(self.strategy);
   }
 
 }
 
-pub fn run<'a, I>(lines: I) -> Result<(u32, u32), &'static str>
+pub fn parse<I>(lines: I) -> Result<Vec<Entry>, Error>
   where I: Iterator< Item = String >
 {
-  let mut state = State::new();
-  let mut state2 = State2::new();
-  for line in lines {
-    match Entry::from_str(&line) {
-      Ok(entry) => {
-        state.step(&entry);
-        state2.step(&entry);
-      },
-      Err(e) => {
-        return Err(e);
-      }
-    }
+  error::parse_lines(lines)
+}
+
+/// The total score when reading the guide with `strategy`.
+pub fn total_score(entries: &[Entry], strategy: Strategy) -> u32 {
+  let mut state = State::new(strategy);
+  for entry in entries {
+    state.step(entry);
   }
-  Ok((state.score, state2.score))
+  state.score
 }
 
-pub fn file_lines(path: &str) -> Box<dyn Iterator<Item = String>> {
-  let file = File::open(path).unwrap();
-  let lines = io::BufReader::new(file).lines().into_iter().map(|l| l.unwrap());
-  Box::new(lines)
+/// The total score under each part's strategy: the second column as a move,
+/// then as an outcome.
+pub fn solve(entries: &[Entry]) -> (u32, u32) {
+  (total_score(entries, Strategy::Move), total_score(entries, Strategy::Outcome))
 }
 
-#[derive(Debug)]
-struct State2 {
-  score: u32,
+pub fn run<I>(lines: I) -> Result<(u32, u32), Error>
+  where I: Iterator< Item = String >
+{
+  Ok(solve(&parse(lines)?))
 }
 
-impl State2 {
-  pub fn new() -> State2 {
-    State2 { score: 0}
+pub struct Day;
+
+impl Solution for Day {
+  const DAY: u8 = 2;
+  const INPUT_FILE: &'static str = INPUT_FILE;
+  type Input = Vec<Entry>;
+  type Answer1 = u32;
+  type Answer2 = u32;
+
+  fn parse(input: &str) -> Result<Vec<Entry>, Error> {
+    parse(input.lines().map(String::from))
   }
 
-  pub fn step(&mut self, entry: &Entry) {
+  fn part_1(entries: &Vec<Entry>) -> Result<u32, Error> {
+    Ok(total_score(entries, Strategy::Move))
   }
 
+  fn part_2(entries: &Vec<Entry>) -> Result<u32, Error> {
+    Ok(total_score(entries, Strategy::Outcome))
+  }
 }
 
-/*
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::input::{real_input, Input};
+
+  const EXAMPLE: &str = "A Y\nB X\nC Z\n";
+
+  #[test]
+  fn d2_parse() {
+    assert_eq!(Entry::from_str("A Y"), Ok(Entry { them: Play::Rock, column: Column::Y }));
+    assert_eq!(Entry::from_str("C X"), Ok(Entry { them: Play::Scissors, column: Column::X }));
+    assert_eq!(Entry::from_str(""), Err(ErrorKind::MissingField("opponent's play")));
+    assert_eq!(Entry::from_str("A"), Err(ErrorKind::MissingField("space")));
+    assert_eq!(Entry::from_str("A "), Err(ErrorKind::MissingField("response")));
+    assert_eq!(Entry::from_str("AY"), Err(ErrorKind::UnexpectedChar { column: 2, found: 'Y' }));
+    assert_eq!(Entry::from_str("D Y"), Err(ErrorKind::UnexpectedChar { column: 1, found: 'D' }));
+    assert_eq!(Entry::from_str("A W"), Err(ErrorKind::UnexpectedChar { column: 3, found: 'W' }));
+    assert_eq!(Entry::from_str("A Y junk"), Err(ErrorKind::UnexpectedChar { column: 4, found: ' ' }));
+    assert_eq!(Entry::from_str("A YZ"), Err(ErrorKind::UnexpectedChar { column: 4, found: 'Z' }));
+  }
+
+  #[test]
+  fn d2_strategies() {
+    let them = Play::Rock;
+    assert_eq!(Strategy::Move.my_play(&them, Column::X), Play::Rock);
+    assert_eq!(Strategy::Move.my_play(&them, Column::Z), Play::Scissors);
+    assert_eq!(Strategy::Outcome.my_play(&them, Column::X), Play::Scissors);
+    assert_eq!(Strategy::Outcome.my_play(&them, Column::Y), Play::Rock);
+    assert_eq!(Strategy::Outcome.my_play(&them, Column::Z), Play::Paper);
+  }
+
+  #[test]
+  fn d2_scores() {
+    let entry = Entry::from_str("A Y").unwrap();
+    assert_eq!(entry.score(Strategy::Move), 8);
+    assert_eq!(entry.score(Strategy::Outcome), 4);
+    let entry = Entry::from_str("C Z").unwrap();
+    assert_eq!(entry.score(Strategy::Move), 6);
+    assert_eq!(entry.score(Strategy::Outcome), 7);
+  }
+
+  #[test]
+  fn d2_example() {
+    assert_eq!(run(Input::from(EXAMPLE).lines()), Ok((15, 12)));
+  }
+
+  #[test]
+  fn d2_empty() {
+    assert_eq!(run(Input::from("").lines()), Ok((0, 0)));
+  }
+
+  #[test]
+  fn d2_malformed_line() {
+    assert_eq!(
+      run(Input::from("A Y\nB  X\nC Z").lines()),
+      Err(ErrorKind::UnexpectedChar { column: 3, found: ' ' }.at(2, "B  X"))
+    );
+  }
 
   #[test]
   fn d2_real() {
-    let input = file_lines(input_file);
-    assert_eq!(run(input.into_iter()), Ok((11258, 0)));
+    // Only the outcome strategy's answer is known; see answers.txt.
+    let Some(input) = real_input(INPUT_FILE) else { return };
+    assert_eq!(run(input.lines()).map(|(_, outcome)| outcome), Ok(11258));
   }
 
 }
-*/
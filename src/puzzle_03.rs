@@ -1,111 +1,501 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fmt;
+use std::num::NonZeroUsize;
 use std::str::FromStr;
-use std::collections::hash_map::RandomState;
 
-const input_file : &'static str = "inputs/puzzle_3.txt";
+use crate::error::{Error, ErrorKind};
+use crate::solution::Solution;
 
-#[derive(PartialEq, Eq, Debug, Clone, Hash)]
-struct Item { item_code: char }
+pub const INPUT_FILE : &str = "inputs/puzzle_3.txt";
+
+/// One kind of item: always a letter, `a`–`z` or `A`–`Z`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct Item { item_code: char }
+
+/// How many compartments a rucksack has, as the puzzle describes it.
+pub const COMPARTMENTS : NonZeroUsize = NonZeroUsize::new(2).unwrap();
+
+/// How many elves share a badge, as the puzzle describes it.
+pub const GROUP_SIZE : NonZeroUsize = NonZeroUsize::new(3).unwrap();
 
 #[derive(PartialEq, Eq, Debug)]
 struct Rucksack {
-    left_items: Vec<Item>,
-    right_items: Vec<Item>,
+    compartments: Vec<ItemSet>,
 }
 
 impl FromStr for Rucksack {
-    type Err = &'static str;
+    type Err = ErrorKind;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.chars().all(|c| s.is_ascii()) {
-            let mut left_items : Vec<Item> = s.chars().into_iter().map(|item_code| Item{ item_code }).collect();
-            let right_items = left_items.split_off(s.chars().into_iter().collect::<Vec<_>>().len() / 2);
-            Ok(Rucksack {
-                left_items, right_items
-            })
-        } else {
-            Err("Encountered non-ascii chars")
-        }
+        Rucksack::parse(s, COMPARTMENTS)
     }
 }
 
 impl Item {
+    /// The item for a letter; anything else isn't an item.
+    pub fn new(item_code: char) -> Option<Item> {
+        item_code.is_ascii_alphabetic().then_some(Item { item_code })
+    }
+
+    pub fn code(&self) -> char {
+        self.item_code
+    }
+
     pub fn priority(&self) -> i32 {
         let c = self.item_code;
-        if c.is_ascii() {
-            if self.item_code.is_ascii_lowercase() {
-                c as i32 - 'a' as i32 + 1
-            } else if self.item_code.is_ascii_uppercase() {
-                c as i32 - 'A' as i32 + 1 + 26
-            } else {
-                0
-            }
+        if c.is_ascii_lowercase() {
+            c as i32 - 'a' as i32 + 1
         } else {
-            0
+            c as i32 - 'A' as i32 + 1 + 26
         }
     }
+
+    /// The letter with this priority, 1 to 52.
+    pub fn from_priority(priority: i32) -> Option<Item> {
+        let item_code = match priority {
+            1..=26 => (b'a' + (priority - 1) as u8) as char,
+            27..=52 => (b'A' + (priority - 27) as u8) as char,
+            _ => return None,
+        };
+        Some(Item { item_code })
+    }
+}
+
+/// A set of item types, one bit per priority.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ItemSet {
+    bits: u64,
+}
+
+impl ItemSet {
+    pub fn new() -> ItemSet {
+        ItemSet::default()
+    }
+
+    fn bit(item: Item) -> u64 {
+        1 << item.priority()
+    }
+
+    pub fn insert(&mut self, item: Item) {
+        self.bits |= ItemSet::bit(item);
+    }
+
+    pub fn contains(&self, item: Item) -> bool {
+        self.bits & ItemSet::bit(item) != 0
+    }
+
+    pub fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet { bits: self.bits & other.bits }
+    }
+
+    pub fn union(self, other: ItemSet) -> ItemSet {
+        ItemSet { bits: self.bits | other.bits }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// The items, lowest priority first.
+    pub fn iter(&self) -> impl Iterator<Item = Item> + '_ {
+        (1..=52).filter(|&p| self.bits & (1 << p) != 0).filter_map(Item::from_priority)
+    }
+
+    /// The total priority of every item in the set.
+    pub fn priority(&self) -> i32 {
+        self.iter().map(|item| item.priority()).sum()
+    }
+
+    /// The item, if the set holds exactly one.
+    pub fn only(&self) -> Option<Item> {
+        if self.len() == 1 {
+            Item::from_priority(self.bits.trailing_zeros() as i32)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromIterator<Item> for ItemSet {
+    fn from_iter<I: IntoIterator<Item = Item>>(items: I) -> ItemSet {
+        let mut set = ItemSet::new();
+        for item in items {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+/// Every character must be an item.
+impl FromStr for ItemSet {
+    type Err = ErrorKind;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .enumerate()
+            .map(|(i, c)| Item::new(c).ok_or(ErrorKind::UnexpectedChar { column: i + 1, found: c }))
+            .collect()
+    }
+}
+
+/// Check a line holds nothing but items, and at least one.
+fn check_line(s: &str) -> Result<(), ErrorKind> {
+    if s.is_empty() {
+        return Err(ErrorKind::MissingField("items"));
+    }
+    match s.chars().enumerate().find(|&(_, c)| Item::new(c).is_none()) {
+        Some((i, found)) => Err(ErrorKind::UnexpectedChar { column: i + 1, found }),
+        None => Ok(()),
+    }
+}
+
+/// The items in every one of `sets`.
+fn common(sets: impl IntoIterator<Item = ItemSet>) -> ItemSet {
+    sets.into_iter().reduce(ItemSet::intersection).unwrap_or_default()
+}
+
+/// The one item of `set`, or `none`/`several` when there isn't just one.
+fn only_item(set: ItemSet, none: ErrorKind, several: ErrorKind) -> Result<Item, ErrorKind> {
+    match set.len() {
+        0 => Err(none),
+        1 => Ok(set.only().expect("one item")),
+        _ => Err(several),
+    }
 }
 
 impl Rucksack {
-    pub fn misplaced_item(&self) -> Result<Item, &'static str> {
-        let left_set : HashSet<&Item,RandomState> = self.left_items.iter().collect();
-        let right_set : HashSet<&Item,_> = self.right_items.iter().collect();
-        let misplaced_items : Vec<&Item> = left_set.intersection(&right_set).into_iter().cloned().collect();
-        match misplaced_items.len() {
-            1 => Ok(misplaced_items[0].clone()),
-            0 => Err("No misplaced items"),
-            _ => Err("Multiple misplaced items"),
+    /// Split the items into `compartments` equal compartments.
+    pub fn parse(s: &str, compartments: NonZeroUsize) -> Result<Rucksack, ErrorKind> {
+        let compartments = compartments.get();
+        check_line(s)?;
+        // All letters, so every item is one byte.
+        if !s.len().is_multiple_of(compartments) {
+            return Err(ErrorKind::UnevenCompartments { items: s.len(), compartments });
+        }
+        let size = s.len() / compartments;
+        let compartments = (0..compartments)
+            .map(|i| ItemSet::from_str(&s[i * size..(i + 1) * size]).expect("checked above"))
+            .collect();
+        Ok(Rucksack { compartments })
+    }
+
+    /// The one item found in every compartment.
+    pub fn misplaced_item(&self) -> Result<Item, ErrorKind> {
+        let shared = common(self.compartments.iter().copied());
+        only_item(shared, ErrorKind::NoCommonItem, ErrorKind::MultipleCommonItems)
+    }
+
+    /// Everything in the rucksack, whichever compartment it's in.
+    pub fn contents(&self) -> ItemSet {
+        self.compartments.iter().fold(ItemSet::new(), |all, &c| all.union(c))
+    }
+}
+
+/// The items shared by one rucksack's compartments, or by one group's
+/// rucksacks, and the lines they came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Share {
+    /// 1-based input lines: one rucksack, or a group's worth.
+    pub lines: Vec<usize>,
+    pub shared: ItemSet,
+}
+
+impl Share {
+    /// What this adds to the total: the shared item's priority when
+    /// there's exactly one, as the puzzle expects.
+    pub fn contribution(&self) -> i32 {
+        self.shared.only().map_or(0, |item| item.priority())
+    }
+}
+
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.lines.as_slice() {
+            [line] => write!(f, "line {line}:")?,
+            [first, .., last] => write!(f, "lines {first}-{last}:")?,
+            [] => write!(f, "no lines:")?,
+        }
+        if self.shared.is_empty() {
+            return write!(f, " nothing shared");
+        }
+        for item in self.shared.iter() {
+            write!(f, " {} ({})", item.code(), item.priority())?;
+        }
+        if self.shared.len() > 1 {
+            write!(f, ", ambiguous")?;
+        }
+        Ok(())
+    }
+}
+
+/// What every rucksack's compartments share. Only lines that can't be read
+/// as rucksacks are errors; sharing nothing or too much is left for the
+/// reader to see.
+pub fn rucksack_shares<I>(lines: I, compartments: NonZeroUsize) -> Result<Vec<Share>, Error>
+  where I: Iterator< Item = String >
+{
+    lines
+        .enumerate()
+        .map(|(i, line)| {
+            let rucksack = Rucksack::parse(&line, compartments).map_err(|kind| kind.at(i + 1, &line))?;
+            Ok(Share { lines: vec![i + 1], shared: common(rucksack.compartments) })
+        })
+        .collect()
+}
+
+/// Call `f` with each group's first line, its text, and the items the
+/// whole group carries.
+fn each_group<I, S, F>(lines: I, group_size: NonZeroUsize, mut f: F) -> Result<(), Error>
+  where I: Iterator< Item = S >,
+        S: AsRef<str>,
+        F: FnMut(usize, &str, ItemSet) -> Result<(), Error>
+{
+    let group_size = group_size.get();
+    // The group's first line, and the items everyone so far carries.
+    let mut group : Option<(usize, S, ItemSet)> = None;
+    let mut members = 0;
+    for (i, line) in lines.enumerate() {
+        let text = line.as_ref();
+        let items = check_line(text)
+            .and_then(|_| ItemSet::from_str(text))
+            .map_err(|kind| kind.at(i + 1, text))?;
+        let (first_line, first_text, shared) = group.get_or_insert_with(|| (i + 1, line, ItemSet { bits: !0 }));
+        *shared = shared.intersection(items);
+        members += 1;
+        if members == group_size {
+            f(*first_line, first_text.as_ref(), *shared)?;
+            group = None;
+            members = 0;
+        }
+    }
+    if let Some((first_line, first_text, _)) = group {
+        return Err(ErrorKind::IncompleteGroup { size: group_size, found: members }.at(first_line, first_text.as_ref()));
+    }
+    Ok(())
+}
+
+/// What every group of `group_size` elves carries in common. An
+/// incomplete last group is an error.
+pub fn group_shares<I>(lines: I, group_size: NonZeroUsize) -> Result<Vec<Share>, Error>
+  where I: Iterator< Item = String >
+{
+    let mut shares = Vec::new();
+    each_group(lines, group_size, |first_line, _, shared| {
+        shares.push(Share { lines: (first_line..first_line + group_size.get()).collect(), shared });
+        Ok(())
+    })?;
+    Ok(shares)
+}
+
+/// One line per share, then the total, for checking the answer by hand.
+pub fn explain(shares: &[Share]) -> String {
+    let mut out = String::new();
+    for share in shares {
+        out.push_str(&format!("{share}\n"));
+    }
+    let total: i32 = shares.iter().map(Share::contribution).sum();
+    out.push_str(&format!("total: {total}\n"));
+    out
+}
+
+/// Every rucksack in the input, read once and then open to questions. Elves
+/// are known by the 1-based line their rucksack is on.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Inventory {
+    /// The input, split into compartments as each part needs and shown
+    /// alongside errors.
+    lines: Vec<String>,
+    rucksacks: Vec<Rucksack>,
+}
+
+impl Inventory {
+    pub fn parse<I>(lines: I, compartments: NonZeroUsize) -> Result<Inventory, Error>
+      where I: Iterator< Item = String >
+    {
+        let lines : Vec<String> = lines.collect();
+        let rucksacks = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| Rucksack::parse(line, compartments).map_err(|kind| kind.at(i + 1, line)))
+            .collect::<Result<Vec<Rucksack>, Error>>()?;
+        Ok(Inventory { lines, rucksacks })
+    }
+
+    /// Sum the priorities of the misplaced items, splitting each rucksack
+    /// into `compartments`.
+    pub fn misplaced_total(&self, compartments: NonZeroUsize) -> Result<i32, Error> {
+        misplaced_total(self.lines.iter(), compartments)
+    }
+
+    /// Sum the priorities of each group's badge, for groups of
+    /// `group_size` elves.
+    pub fn badge_total(&self, group_size: NonZeroUsize) -> Result<i32, Error> {
+        badge_total(self.lines.iter(), group_size)
+    }
+
+    /// How many elves there are.
+    pub fn len(&self) -> usize {
+        self.rucksacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rucksacks.is_empty()
+    }
+
+    /// What an elf carries.
+    pub fn contents(&self, elf: usize) -> Option<ItemSet> {
+        self.rucksacks.get(elf.checked_sub(1)?).map(Rucksack::contents)
+    }
+
+    /// The elves carrying `item`, in order.
+    pub fn carriers(&self, item: Item) -> Vec<usize> {
+        self.elves().filter(|&(_, items)| items.contains(item)).map(|(elf, _)| elf).collect()
+    }
+
+    /// The items every elf carries.
+    pub fn in_every_rucksack(&self) -> ItemSet {
+        common(self.elves().map(|(_, items)| items))
+    }
+
+    /// How many rucksacks hold each item that turns up at all, in priority
+    /// order.
+    pub fn frequencies(&self) -> Vec<(Item, usize)> {
+        let mut counts = [0; 53];
+        for (_, items) in self.elves() {
+            for item in items.iter() {
+                counts[item.priority() as usize] += 1;
+            }
         }
+        (1..=52)
+            .filter(|&p| counts[p] > 0)
+            .filter_map(|p| Some((Item::from_priority(p as i32)?, counts[p])))
+            .collect()
     }
 
+    /// Each pair of elves with at least `k` kinds of item in common, and
+    /// what they share.
+    pub fn sharing_at_least(&self, k: usize) -> Vec<(usize, usize, ItemSet)> {
+        let elves : Vec<(usize, ItemSet)> = self.elves().collect();
+        let mut pairs = Vec::new();
+        for (i, &(elf_1, items_1)) in elves.iter().enumerate() {
+            for &(elf_2, items_2) in &elves[i + 1..] {
+                let shared = items_1.intersection(items_2);
+                if shared.len() >= k {
+                    pairs.push((elf_1, elf_2, shared));
+                }
+            }
+        }
+        pairs
+    }
 
+    fn elves(&self) -> impl Iterator<Item = (usize, ItemSet)> + '_ {
+        self.rucksacks.iter().enumerate().map(|(i, r)| (i + 1, r.contents()))
+    }
 }
 
+/// Rucksacks with the puzzle's two compartments.
+impl FromStr for Inventory {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Inventory::parse(s.lines().map(String::from), COMPARTMENTS)
+    }
+}
 
-pub fn file_lines(path: &str) -> Box<dyn Iterator<Item = String>> {
-  let file = File::open(path).unwrap();
-  let lines = io::BufReader::new(file).lines().into_iter().map(|l| l.unwrap());
-  Box::new(lines)
+pub fn run<I>(lines: I) -> Result<i32, Error>
+  where I: Iterator< Item = String >
+{
+    run_with(lines, COMPARTMENTS)
 }
 
-pub fn run<'a, I>(lines: I) -> Result<i32, &'static str>
+/// Sum the priorities of the misplaced items, for rucksacks with the given
+/// number of compartments.
+pub fn run_with<I>(lines: I, compartments: NonZeroUsize) -> Result<i32, Error>
   where I: Iterator< Item = String >
+{
+    misplaced_total(lines, compartments)
+}
+
+fn misplaced_total<I, S>(lines: I, compartments: NonZeroUsize) -> Result<i32, Error>
+  where I: Iterator< Item = S >,
+        S: AsRef<str>
 {
     let mut total_value = 0;
-    for line in lines {
-      let priority =  Rucksack::from_str(&line)?.misplaced_item()?.priority();
+    for (i, line) in lines.enumerate() {
+      let line = line.as_ref();
+      let priority = Rucksack::parse(line, compartments)
+          .and_then(|r| r.misplaced_item())
+          .map_err(|kind| kind.at(i + 1, line))?
+          .priority();
       total_value += priority;
     }
     Ok(total_value)
 }
 
-pub fn run_2<'a, I>(mut lines: I) -> Result<i32, &'static str>
+pub fn run_2<I>(lines: I) -> Result<i32, Error>
   where I: Iterator< Item = String >
+{
+    run_2_with(lines, GROUP_SIZE)
+}
+
+/// Sum the priorities of each group's badge, the one item every elf in the
+/// group carries, for groups of `group_size` elves. Errors are reported at
+/// the group's first line.
+pub fn run_2_with<I>(lines: I, group_size: NonZeroUsize) -> Result<i32, Error>
+  where I: Iterator< Item = String >
+{
+    badge_total(lines, group_size)
+}
+
+fn badge_total<I, S>(lines: I, group_size: NonZeroUsize) -> Result<i32, Error>
+  where I: Iterator< Item = S >,
+        S: AsRef<str>
 {
     let mut total_group_priority = 0;
-    loop {
-        match lines.next() {
-            None => { break; },
-            Some(elf_1) => {
-                let elf_2 = lines.next().expect("elf should exist");
-                let elf_3 = lines.next().expect("elf should exist");
-                let items_1 = elf_1.chars().into_iter().collect::<HashSet::<char, RandomState>>();
-                let items_2 = elf_2.chars().into_iter().collect::<HashSet::<char, RandomState>>();
-                let items_3 = elf_3.chars().into_iter().collect::<HashSet::<char, RandomState>>();
-                let commons = items_1.intersection( &items_2 ).into_iter().cloned().collect::<HashSet::<char, RandomState>>().intersection( &items_3 ).into_iter().cloned().collect::<Vec<char>>();
-                assert!(commons.len() == 1);
-                total_group_priority += Item{item_code: commons[0]}.priority()
-            }
-        }
-    }
+    each_group(lines, group_size, |first_line, first_text, shared| {
+        let badge = only_item(shared, ErrorKind::NoGroupItem, ErrorKind::MultipleGroupItems)
+            .map_err(|kind| kind.at(first_line, first_text))?;
+        total_group_priority += badge.priority();
+        Ok(())
+    })?;
     Ok(total_group_priority)
 }
 
-/*
+pub struct Day;
+
+impl Solution for Day {
+    const DAY: u8 = 3;
+    const INPUT_FILE: &'static str = INPUT_FILE;
+    type Input = Inventory;
+    type Answer1 = i32;
+    type Answer2 = i32;
+
+    /// Rucksacks are only split into compartments for part 1.
+    fn parse(input: &str) -> Result<Inventory, Error> {
+        Inventory::parse(input.lines().map(String::from), NonZeroUsize::MIN)
+    }
+
+    fn part_1(inventory: &Inventory) -> Result<i32, Error> {
+        inventory.misplaced_total(COMPARTMENTS)
+    }
+
+    fn part_2(inventory: &Inventory) -> Result<i32, Error> {
+        inventory.badge_total(GROUP_SIZE)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{nonzero, real_input, Input};
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
 
     #[test]
     fn d3_priorities() {
@@ -115,12 +505,92 @@ mod tests {
         assert_eq!( Item{item_code: 'Z'}.priority(), 52);
     }
 
+    fn items(s: &str) -> ItemSet {
+        ItemSet::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn d3_item_sets() {
+        let set = items("abcA");
+        assert_eq!( set.len(), 4 );
+        assert!( set.contains(Item{item_code: 'A'}) && !set.contains(Item{item_code: 'B'}) );
+        assert_eq!( set.intersection(items("cAx")), items("Ac") );
+        assert_eq!( set.union(items("Z")).len(), 5 );
+        assert_eq!( set.iter().map(|i| i.code()).collect::<String>(), "abcA" );
+        assert_eq!( set.priority(), 1 + 2 + 3 + 27 );
+        assert_eq!( items("zZ").intersection(items("Z")).only(), Some(Item{item_code: 'Z'}) );
+        assert_eq!( set.only(), None );
+        assert!( ItemSet::new().is_empty() );
+        assert_eq!( Item::from_priority(52), Some(Item{item_code: 'Z'}) );
+        assert_eq!( Item::from_priority(0), None );
+    }
+
+    #[test]
+    fn d3_many_rucksacks() {
+        let lines = (0..100_000).map(|i| if i % 2 == 0 { "vJrwpWtwJgWrhcsFMMfFFhFp" } else { "PmmdzqPrVvPwwTWBwg" }.to_string());
+        assert_eq!(run(lines), Ok(50_000 * (16 + 42)));
+    }
+
     #[test]
     fn d3_parse() {
-        assert_eq!( Rucksack::from_str("abcdEF").unwrap(), Rucksack {
-            left_items: "abc".chars().into_iter().map(|item_code| Item{item_code}).collect(),
-            right_items: "dEF".chars().into_iter().map(|item_code| Item{item_code}).collect(),
-        } )
+        assert_eq!( Rucksack::from_str("abcdEF").unwrap(), Rucksack { compartments: vec![items("abc"), items("dEF")] } );
+        assert_eq!( Rucksack::parse("abcdEF", nonzero(3)).unwrap(), Rucksack { compartments: vec![items("ab"), items("cd"), items("EF")] } );
+    }
+
+    #[test]
+    fn d3_uneven_compartments() {
+        assert_eq!( Rucksack::from_str("abc"), Err(ErrorKind::UnevenCompartments { items: 3, compartments: 2 }) );
+        assert_eq!( Rucksack::parse("abcd", nonzero(3)), Err(ErrorKind::UnevenCompartments { items: 4, compartments: 3 }) );
+        assert_eq!( run(Input::from("aa\nabcba").lines()), Err(ErrorKind::UnevenCompartments { items: 5, compartments: 2 }.at(2, "abcba")) );
+    }
+
+    #[test]
+    fn d3_more_compartments() {
+        assert_eq!( Rucksack::parse("abacaB", nonzero(3)).unwrap().misplaced_item(), Ok(Item{item_code: 'a'}) );
+        assert_eq!( Rucksack::parse("abAcdB", nonzero(3)).unwrap().misplaced_item(), Err(ErrorKind::NoCommonItem) );
+        assert_eq!( run_with(Input::from("abAcaB\nxx").lines(), nonzero(1)), Err(ErrorKind::MultipleCommonItems.at(1, "abAcaB")) );
+        assert_eq!( run_with(Input::from("abacaB\nxyzxzx").lines(), nonzero(3)), Ok(1 + 24) );
+    }
+
+    #[test]
+    fn d3_group_sizes() {
+        assert_eq!( run_2_with(Input::from("ab\nbc\ncd\nda").lines(), nonzero(2)), Ok(2 + 4) );
+        assert_eq!( run_2_with(Input::from(EXAMPLE).lines(), nonzero(1)), Err(ErrorKind::MultipleGroupItems.at(1, "vJrwpWtwJgWrhcsFMMfFFhFp")) );
+        assert_eq!( run_2_with(Input::from(EXAMPLE).lines(), nonzero(6)), Err(ErrorKind::NoGroupItem.at(1, "vJrwpWtwJgWrhcsFMMfFFhFp")) );
+    }
+
+    #[test]
+    fn d3_group_errors() {
+        let input = Input::from(EXAMPLE);
+        assert_eq!( run_2(input.lines().take(4)), Err(ErrorKind::IncompleteGroup { size: 3, found: 1 }.at(4, "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn")) );
+        assert_eq!( run_2_with(Input::from("ab\nbc\nab\ncd").lines(), nonzero(2)), Err(ErrorKind::NoGroupItem.at(3, "ab")) );
+        assert_eq!( run_2_with(Input::from("ab\nab").lines(), nonzero(2)), Err(ErrorKind::MultipleGroupItems.at(1, "ab")) );
+    }
+
+    #[test]
+    fn d3_parse_non_ascii() {
+        assert_eq!( Rucksack::from_str("abcé"), Err(ErrorKind::UnexpectedChar { column: 4, found: 'é' }) );
+    }
+
+    #[test]
+    fn d3_parse_non_letters() {
+        assert_eq!( Rucksack::from_str("ab1b"), Err(ErrorKind::UnexpectedChar { column: 3, found: '1' }) );
+        assert_eq!( Rucksack::from_str("a b "), Err(ErrorKind::UnexpectedChar { column: 2, found: ' ' }) );
+        assert_eq!( Rucksack::from_str("ab.."), Err(ErrorKind::UnexpectedChar { column: 3, found: '.' }) );
+        assert_eq!( Rucksack::from_str(""), Err(ErrorKind::MissingField("items")) );
+        assert_eq!( ItemSet::from_str("aZ!"), Err(ErrorKind::UnexpectedChar { column: 3, found: '!' }) );
+        assert_eq!( run(Input::from("vJrwpWtwJgWrhcsFMMfFFhFp\n\nabca").lines()), Err(ErrorKind::MissingField("items").at(2, "")) );
+        assert_eq!( run_2(Input::from("ab\nb-\nb").lines()), Err(ErrorKind::UnexpectedChar { column: 2, found: '-' }.at(2, "b-")) );
+        assert_eq!( run_2(Input::from("ab\n\nb").lines()), Err(ErrorKind::MissingField("items").at(2, "")) );
+    }
+
+    #[test]
+    fn d3_items_are_letters() {
+        assert_eq!( Item::new('q').map(|i| i.priority()), Some(17) );
+        assert_eq!( Item::new('Q').map(|i| i.priority()), Some(43) );
+        assert_eq!( Item::new('1'), None );
+        assert_eq!( Item::new('é'), None );
+        assert!( (1..=52).all(|p| Item::from_priority(p).map(|i| i.priority()) == Some(p)) );
     }
 
     #[test]
@@ -134,29 +604,112 @@ mod tests {
         assert_eq!( Rucksack::from_str("CrZsJsPPZsGzwwsLwLmpwMDw").unwrap().misplaced_item().unwrap(), Item{item_code: 's'} );
     }
 
+    #[test]
+    fn d3_misplaced_item_errors() {
+        assert_eq!( Rucksack::from_str("abcd").unwrap().misplaced_item(), Err(ErrorKind::NoCommonItem) );
+        assert_eq!( Rucksack::from_str("abab").unwrap().misplaced_item(), Err(ErrorKind::MultipleCommonItems) );
+    }
+
+    #[test]
+    fn d3_shares() {
+        let shares = rucksack_shares(Input::from("vJrwpWtwJgWrhcsFMMfFFhFp\nabcd\nabab").lines(), COMPARTMENTS).unwrap();
+        assert_eq!( shares[0], Share { lines: vec![1], shared: items("p") } );
+        assert_eq!( shares[1].shared, ItemSet::new() );
+        assert_eq!( shares[2].shared, items("ab") );
+        assert_eq!( shares.iter().map(Share::contribution).collect::<Vec<i32>>(), vec![16, 0, 0] );
+        assert_eq!( rucksack_shares(Input::from("a\nabc").lines(), nonzero(1)).unwrap()[1].shared, items("abc") );
+        assert!( rucksack_shares(Input::from("abc").lines(), nonzero(2)).is_err() );
+
+        let groups = group_shares(Input::from(EXAMPLE).lines(), GROUP_SIZE).unwrap();
+        assert_eq!( groups, vec![
+            Share { lines: vec![1, 2, 3], shared: items("r") },
+            Share { lines: vec![4, 5, 6], shared: items("Z") },
+        ] );
+        assert!( group_shares(Input::from(EXAMPLE).lines(), nonzero(4)).is_err() );
+    }
+
+    #[test]
+    fn d3_explain() {
+        let shares = rucksack_shares(Input::from("vJrwpWtwJgWrhcsFMMfFFhFp\nabcd\nabab").lines(), COMPARTMENTS).unwrap();
+        assert_eq!( explain(&shares), "line 1: p (16)\nline 2: nothing shared\nline 3: a (1) b (2), ambiguous\ntotal: 16\n" );
+        let groups = group_shares(Input::from(EXAMPLE).lines(), GROUP_SIZE).unwrap();
+        assert_eq!( explain(&groups), "lines 1-3: r (18)\nlines 4-6: Z (52)\ntotal: 70\n" );
+    }
+
+    #[test]
+    fn d3_inventory() {
+        let inventory = Inventory::from_str(EXAMPLE).unwrap();
+        assert_eq!( inventory.len(), 6 );
+        assert_eq!( inventory.contents(5), Some(items("ttgJRGQcTZ")) );
+        assert_eq!( inventory.contents(0), None );
+        assert_eq!( inventory.contents(7), None );
+        assert_eq!( inventory.carriers(Item{item_code: 'Z'}), vec![2, 4, 5, 6] );
+        assert_eq!( inventory.carriers(Item{item_code: 'a'}), Vec::<usize>::new() );
+        assert!( inventory.in_every_rucksack().is_empty() );
+        let frequencies = inventory.frequencies();
+        assert_eq!( frequencies.first(), Some(&(Item{item_code: 'b'}, 1)) );
+        assert!( frequencies.contains(&(Item{item_code: 'c'}, 3)) );
+        assert!( frequencies.contains(&(Item{item_code: 'w'}, 4)) );
+        assert!( frequencies.iter().all(|&(_, n)| n > 0) );
+    }
+
+    #[test]
+    fn d3_inventory_sharing() {
+        let inventory = Inventory::from_str("abcabc\nabcdbd\nxyzxyz").unwrap();
+        assert_eq!( inventory.in_every_rucksack(), ItemSet::new() );
+        assert_eq!( inventory.sharing_at_least(2), vec![(1, 2, items("abc"))] );
+        assert_eq!( inventory.sharing_at_least(0).len(), 3 );
+        assert!( inventory.sharing_at_least(4).is_empty() );
+        let inventory = Inventory::parse(Input::from("aab\naxy").lines(), nonzero(1)).unwrap();
+        assert_eq!( inventory.in_every_rucksack(), items("a") );
+        assert!( Inventory::from_str("abc").is_err() );
+    }
+
+    #[test]
+    fn d3_day() {
+        let inventory = Day::parse(EXAMPLE).unwrap();
+        assert_eq!( Day::part_1(&inventory), Ok(157) );
+        assert_eq!( Day::part_2(&inventory), Ok(70) );
+        let odd = Day::parse("aXb\nXcde\nfgX").unwrap();
+        assert_eq!( Day::part_1(&odd), Err(ErrorKind::UnevenCompartments { items: 3, compartments: 2 }.at(1, "aXb")) );
+        assert_eq!( Day::part_2(&odd), Ok(50) );
+    }
+
     #[test]
     fn d3_example() {
-        let input : Vec<String> = vec![
-            "vJrwpWtwJgWrhcsFMMfFFhFp" ,
-            "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
-            "PmmdzqPrVvPwwTWBwg",
-            "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
-            "ttgJtRGJQctTZtZT",
-            "CrZsJsPPZsGzwwsLwLmpwMDw",
-        ].into_iter().map(|s| s.to_string()).collect();
-        assert_eq!(run(input.into_iter()).unwrap(), 157);
+        assert_eq!(run(Input::from(EXAMPLE).lines()).unwrap(), 157);
+    }
+
+    #[test]
+    fn d3_example_2() {
+        assert_eq!(run_2(Input::from(EXAMPLE).lines()).unwrap(), 70);
+    }
+
+    #[test]
+    fn d3_empty() {
+        assert_eq!(run(Input::from("").lines()), Ok(0));
+        assert_eq!(run_2(Input::from("").lines()), Ok(0));
+    }
+
+    #[test]
+    fn d3_malformed_line() {
+        assert_eq!(
+            run(Input::from("vJrwpWtwJgWrhcsFMMfFFhFp\nabcd").lines()),
+            Err(ErrorKind::NoCommonItem.at(2, "abcd"))
+        );
+        // The first bad line is reported, even if a later one can't be read.
+        assert_eq!(run(Input::from("abcd\nabc").lines()), Err(ErrorKind::NoCommonItem.at(1, "abcd")));
     }
 
     #[test]
     fn d3_real_1() {
-        let input = file_lines(input_file);
-        assert_eq!(run(input.into_iter()).unwrap(), 7701);
+        let Some(input) = real_input(INPUT_FILE) else { return };
+        assert_eq!(run(input.lines()).unwrap(), 7701);
     }
 
     #[test]
     fn d3_real_2() {
-        let input = file_lines(input_file);
-        assert_eq!(run_2(input.into_iter()).unwrap(), 2644);
+        let Some(input) = real_input(INPUT_FILE) else { return };
+        assert_eq!(run_2(input.lines()).unwrap(), 2644);
     }
 }
-*/
@@ -1,48 +1,253 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::char;
-use std::io::{self, BufRead};
+use std::num::NonZeroUsize;
 use std::str::FromStr;
-use std::collections::hash_map::RandomState;
 
-const input_file : &'static str = "inputs/puzzle_4.txt";
+use crate::error::{self, Error, ErrorKind};
+use crate::solution::Solution;
+use crate::trace;
 
-#[derive(Clone,Debug, PartialEq)]
-struct Assignment {
+pub const INPUT_FILE : &str = "inputs/puzzle_4.txt";
+
+/// The sections one elf cleans, `first` to `last` inclusive. An assignment
+/// whose `last` comes before its `first` covers nothing, though parsing
+/// rejects one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Assignment {
     first: i32,
     last: i32,
 }
 
 #[derive(Clone,Debug, PartialEq)]
-struct Pair {
+pub struct Pair {
    elf_1: Assignment,
    elf_2: Assignment,
 }
 
+impl FromStr for Assignment {
+    type Err = ErrorKind;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, last) = s.rsplit_once('-').ok_or(ErrorKind::MissingDelimiter('-'))?;
+        let (first, last) = (error::number(first)?, error::number(last)?);
+        if last < first {
+            return Err(ErrorKind::ReversedRange { first, last });
+        }
+        Ok(Assignment { first, last })
+    }
+}
+
 impl FromStr for Pair {
-    type Err = &'static str;
+    type Err = ErrorKind;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        println!("{}",s);
-        let (assignment_1, assignment_2) = s.rsplit_once(',').ok_or("Missing comma in first split")?;
-        let (first, last) = assignment_1.rsplit_once('-').ok_or("Nope")?;
-        let elf_1 = Assignment {
-            first: i32::from_str(first).map_err(|_| "Nope")?,
-            last: i32::from_str(last).map_err(|_| "Nope")?,
-        };
-        let (first, last) = assignment_2.rsplit_once('-').ok_or("Nope")?;
-        let elf_2 = Assignment {
-            first: i32::from_str(first).map_err(|_| "Nope")?,
-            last: i32::from_str(last).map_err(|_| "Nope")?,
-        };
+        trace!("{}",s);
+        let (assignment_1, assignment_2) = s.rsplit_once(',').ok_or(ErrorKind::MissingDelimiter(','))?;
+        let elf_1 = Assignment::from_str(assignment_1)?;
+        let elf_2 = Assignment::from_str(assignment_2)?;
         Ok(Pair {elf_1, elf_2})
 
     }
 }
 
 impl Assignment {
+    pub fn new(first: i32, last: i32) -> Assignment {
+        Assignment { first, last }
+    }
+
+    pub fn first(&self) -> i32 {
+        self.first
+    }
+
+    pub fn last(&self) -> i32 {
+        self.last
+    }
+
+    /// How many sections are covered.
+    pub fn len(&self) -> usize {
+        (self.last as i64 - self.first as i64 + 1).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last < self.first
+    }
+
     pub fn contains(&self, other: &Assignment) -> bool {
         self.first <= other.first && self.last >= other.last
     }
+
+    pub fn contains_section(&self, section: i32) -> bool {
+        self.first <= section && section <= self.last
+    }
+
+    pub fn overlaps(&self, other: &Assignment) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The sections both cover, if any.
+    pub fn intersection(&self, other: &Assignment) -> Option<Assignment> {
+        let both = Assignment::new(self.first.max(other.first), self.last.min(other.last));
+        (!both.is_empty()).then_some(both)
+    }
+
+    /// The sections either covers: one range, or two if they're apart.
+    pub fn union(&self, other: &Assignment) -> IntervalSet {
+        IntervalSet::from_iter([*self, *other])
+    }
+
+    /// The sections this covers and `other` doesn't: up to two ranges.
+    pub fn difference(&self, other: &Assignment) -> IntervalSet {
+        IntervalSet::from_iter([*self]).difference(&IntervalSet::from_iter([*other]))
+    }
+}
+
+/// Sections covered by any of a number of assignments, kept as sorted,
+/// separate ranges: overlapping or touching assignments are merged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Assignment>,
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    /// The merged ranges, lowest first.
+    pub fn ranges(&self) -> &[Assignment] {
+        &self.ranges
+    }
+
+    /// How many sections are covered.
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(Assignment::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn contains(&self, section: i32) -> bool {
+        self.ranges.iter().any(|r| r.contains_section(section))
+    }
+
+    /// Add `assignment`, merging it with the ranges it overlaps or touches.
+    pub fn insert(&mut self, assignment: Assignment) {
+        if assignment.is_empty() {
+            return;
+        }
+        // The ranges are apart, so both ends are sorted: the ones to merge
+        // are a run between those wholly before and wholly after.
+        let start = self.ranges.partition_point(|r| (r.last as i64) + 1 < assignment.first as i64);
+        let end = self.ranges.partition_point(|r| r.first as i64 <= assignment.last as i64 + 1);
+        let mut merged = assignment;
+        if start < end {
+            merged.first = merged.first.min(self.ranges[start].first);
+            merged.last = merged.last.max(self.ranges[end - 1].last);
+        }
+        self.ranges.splice(start..end, [merged]);
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        // Both are sorted already, so interleave them and merge as we go.
+        let mut all = Vec::with_capacity(self.ranges.len() + other.ranges.len());
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() || j < other.ranges.len() {
+            if j == other.ranges.len() || (i < self.ranges.len() && self.ranges[i].first <= other.ranges[j].first) {
+                all.push(self.ranges[i]);
+                i += 1;
+            } else {
+                all.push(other.ranges[j]);
+                j += 1;
+            }
+        }
+        IntervalSet { ranges: merge_sorted(all) }
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut both = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            both.ranges.extend(a.intersection(&b));
+            if a.last < b.last { i += 1 } else { j += 1 }
+        }
+        both
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut rest = IntervalSet::new();
+        for range in &self.ranges {
+            let mut first = range.first as i64;
+            for cut in other.ranges.iter().filter(|c| c.overlaps(range)) {
+                if (cut.first as i64) > first {
+                    rest.ranges.push(Assignment::new(first as i32, cut.first - 1));
+                }
+                first = cut.last as i64 + 1;
+            }
+            if first <= range.last as i64 {
+                rest.ranges.push(Assignment::new(first as i32, range.last));
+            }
+        }
+        rest
+    }
+
+    /// The sections between the lowest and highest covered ones that
+    /// aren't covered.
+    pub fn gaps(&self) -> IntervalSet {
+        self.ranges
+            .windows(2)
+            .map(|w| Assignment::new(w[0].last + 1, w[1].first - 1))
+            .collect()
+    }
+}
+
+impl FromIterator<Assignment> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Assignment>>(assignments: I) -> IntervalSet {
+        let mut ranges: Vec<Assignment> = assignments.into_iter().filter(|a| !a.is_empty()).collect();
+        ranges.sort_unstable_by_key(|a| a.first);
+        IntervalSet { ranges: merge_sorted(ranges) }
+    }
+}
+
+/// Merge non-empty ranges sorted by `first` where they overlap or touch.
+fn merge_sorted(ranges: Vec<Assignment>) -> Vec<Assignment> {
+    let mut merged: Vec<Assignment> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.first as i64 <= last.last as i64 + 1 => {
+                last.last = last.last.max(range.last);
+            },
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// The sections that at least `elves` of the assignments cover.
+pub fn covered_by<'a, I>(assignments: I, elves: NonZeroUsize) -> IntervalSet
+  where I: IntoIterator<Item = &'a Assignment>
+{
+    // Sweep along the sections, counting elves in as their assignments
+    // start and out after they end.
+    let mut changes: Vec<(i64, i64)> = assignments
+        .into_iter()
+        .filter(|a| !a.is_empty())
+        .flat_map(|a| [(a.first as i64, 1), (a.last as i64 + 1, -1)])
+        .collect();
+    changes.sort();
+    let mut covered = IntervalSet::new();
+    let elves = elves.get() as i64;
+    let mut count = 0;
+    let mut start = None;
+    for (section, change) in changes {
+        count += change;
+        match start {
+            None if count >= elves => start = Some(section),
+            Some(first) if count < elves => {
+                covered.insert(Assignment::new(first as i32, (section - 1) as i32));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    covered
 }
 
 impl Pair {
@@ -51,32 +256,87 @@ impl Pair {
     }
 
     pub fn has_some_overlap(&self) -> bool {
+        self.elf_1.overlaps(&self.elf_2)
+    }
 
-        !(self.elf_1.last < self.elf_2.first || self.elf_2.last < self.elf_1.first)
-
+    pub fn assignments(&self) -> [&Assignment; 2] {
+        [&self.elf_1, &self.elf_2]
     }
+}
+
+pub fn parse<I>(lines: I) -> Result<Vec<Pair>, Error>
+  where I: Iterator< Item = String >
+{
+    error::parse_lines(lines)
+}
+
+/// Every elf's assignment, across all the pairs.
+pub fn assignments(pairs: &[Pair]) -> impl Iterator<Item = &Assignment> {
+    pairs.iter().flat_map(Pair::assignments)
+}
 
+/// Count the pairs with full containment and the pairs with any overlap.
+pub fn run<I>(lines: I) -> Result<(usize, usize), Error>
+  where I: Iterator< Item = String >
+{
+    let pairs = parse(lines)?;
+    let contained = pairs.iter().filter(|p| p.has_full_containment()).count();
+    let overlapping = pairs.iter().filter(|p| p.has_some_overlap()).count();
+    Ok((contained, overlapping))
 }
 
+pub struct Day;
+
+impl Solution for Day {
+    const DAY: u8 = 4;
+    const INPUT_FILE: &'static str = INPUT_FILE;
+    type Input = Vec<Pair>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<Pair>, Error> {
+        parse(input.lines().map(String::from))
+    }
+
+    fn part_1(pairs: &Vec<Pair>) -> Result<usize, Error> {
+        Ok(pairs.iter().filter(|p| p.has_full_containment()).count())
+    }
 
-pub fn file_lines(path: &str) -> Box<dyn Iterator<Item = String>> {
-  let file = File::open(path).unwrap();
-  let lines = io::BufReader::new(file).lines().into_iter().map(|l| l.unwrap());
-  Box::new(lines)
+    fn part_2(pairs: &Vec<Pair>) -> Result<usize, Error> {
+        Ok(pairs.iter().filter(|p| p.has_some_overlap()).count())
+    }
 }
 
-/*
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{nonzero, real_input, Input};
+
+    const EXAMPLE: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
 
     #[test]
-    fn d3_parse() {
+    fn d4_parse() {
         assert_eq!( Pair::from_str("1-2,3-4").unwrap(), Pair {elf_1: Assignment{first: 1, last: 2}, elf_2: Assignment {first: 3, last: 4}} );
     }
 
     #[test]
-    fn d3_containment() {
+    fn d4_parse_errors() {
+        assert_eq!( Pair::from_str("1-2 3-4"), Err(ErrorKind::MissingDelimiter(',')) );
+        assert_eq!( Pair::from_str("1-2,3"), Err(ErrorKind::MissingDelimiter('-')) );
+        assert_eq!( Pair::from_str("1-2,3-x"), Err(ErrorKind::InvalidNumber("x".to_string())) );
+        assert_eq!( Pair::from_str(""), Err(ErrorKind::MissingDelimiter(',')) );
+        assert_eq!( Pair::from_str("5-1,2-3"), Err(ErrorKind::ReversedRange { first: 5, last: 1 }) );
+        assert_eq!( Pair::from_str("2-3,4-3"), Err(ErrorKind::ReversedRange { first: 4, last: 3 }) );
+    }
+
+    #[test]
+    fn d4_containment() {
         assert!( Pair::from_str("2-4,3-4").unwrap().has_full_containment() );
         assert!( !Pair::from_str("2-4,3-5").unwrap().has_full_containment() );
         assert!( Pair::from_str("3-4,2-4").unwrap().has_full_containment() );
@@ -84,7 +344,7 @@ mod tests {
     }
 
     #[test]
-    fn d3_has_some_overlap() {
+    fn d4_has_some_overlap() {
         assert!( Pair::from_str("2-4,3-4").unwrap().has_some_overlap() );
         assert!( Pair::from_str("3-4,2-4").unwrap().has_some_overlap() );
         assert!( Pair::from_str("1-2,2-3").unwrap().has_some_overlap() );
@@ -98,28 +358,94 @@ mod tests {
     }
 
     #[test]
-    fn d3_example_1() {
-        let input = vec![
-            "2-4,6-8",
-            "2-3,4-5",
-            "5-7,7-9",
-            "2-8,3-7",
-            "6-6,4-6",
-            "2-6,4-8",
-        ].into_iter().map(|s| s.to_string()).collect::<Vec<String>>();
-        assert_eq!(input.into_iter().map(|p| Pair::from_str(&p).expect("parse failed")).filter(|p| p.has_full_containment()).count(), 2);
+    fn d4_range_algebra() {
+        let a = Assignment::new(2, 6);
+        let b = Assignment::new(4, 8);
+        assert_eq!(a.len(), 5);
+        assert_eq!(Assignment::new(3, 2).len(), 0);
+        assert!(Assignment::new(3, 2).is_empty());
+        assert_eq!(a.intersection(&b), Some(Assignment::new(4, 6)));
+        assert_eq!(a.intersection(&Assignment::new(7, 9)), None);
+        assert_eq!(a.union(&b).ranges(), &[Assignment::new(2, 8)]);
+        assert_eq!(a.union(&Assignment::new(7, 9)).ranges(), &[Assignment::new(2, 9)]);
+        assert_eq!(a.union(&Assignment::new(8, 9)).ranges(), &[a, Assignment::new(8, 9)]);
+        assert_eq!(a.difference(&b).ranges(), &[Assignment::new(2, 3)]);
+        assert_eq!(a.difference(&Assignment::new(3, 4)).ranges(), &[Assignment::new(2, 2), Assignment::new(5, 6)]);
+        assert!(a.difference(&Assignment::new(1, 9)).is_empty());
+    }
+
+    #[test]
+    fn d4_interval_sets() {
+        let set = IntervalSet::from_iter([Assignment::new(6, 8), Assignment::new(1, 2), Assignment::new(3, 4), Assignment::new(7, 7)]);
+        assert_eq!(set.ranges(), &[Assignment::new(1, 4), Assignment::new(6, 8)]);
+        assert_eq!(set.len(), 7);
+        assert!(set.contains(8) && !set.contains(5));
+        assert_eq!(set.gaps().ranges(), &[Assignment::new(5, 5)]);
+        let other = IntervalSet::from_iter([Assignment::new(2, 6)]);
+        assert_eq!(set.intersection(&other).ranges(), &[Assignment::new(2, 4), Assignment::new(6, 6)]);
+        assert_eq!(set.difference(&other).ranges(), &[Assignment::new(1, 1), Assignment::new(7, 8)]);
+        assert_eq!(set.union(&other).ranges(), &[Assignment::new(1, 8)]);
+    }
+
+    #[test]
+    fn d4_interval_set_insert() {
+        let mut set = IntervalSet::from_iter([Assignment::new(1, 2), Assignment::new(5, 6), Assignment::new(9, 9)]);
+        set.insert(Assignment::new(4, 4));
+        assert_eq!(set.ranges(), &[Assignment::new(1, 2), Assignment::new(4, 6), Assignment::new(9, 9)]);
+        set.insert(Assignment::new(3, 8));
+        assert_eq!(set.ranges(), &[Assignment::new(1, 9)]);
+        set.insert(Assignment::new(11, 12));
+        set.insert(Assignment::new(0, 0));
+        set.insert(Assignment::new(3, 2));
+        assert_eq!(set.ranges(), &[Assignment::new(0, 9), Assignment::new(11, 12)]);
+    }
+
+    #[test]
+    fn d4_many_assignments() {
+        let set: IntervalSet = (1..=50_000).rev().map(|s| Assignment::new(s * 2, s * 2)).collect();
+        assert_eq!(set.ranges().len(), 50_000);
+        let odd: IntervalSet = (0..50_000).map(|s| Assignment::new(s * 2 + 1, s * 2 + 1)).collect();
+        assert_eq!(set.union(&odd).ranges(), &[Assignment::new(1, 100_000)]);
+    }
+
+    #[test]
+    fn d4_coverage() {
+        let pairs = parse(Input::from(EXAMPLE).lines()).unwrap();
+        let covered = covered_by(assignments(&pairs), nonzero(1));
+        assert_eq!(covered.ranges(), &[Assignment::new(2, 9)]);
+        assert_eq!(covered.len(), 8);
+        assert!(covered.gaps().is_empty());
+        assert_eq!(covered_by(assignments(&pairs), nonzero(3)).ranges(), &[Assignment::new(2, 8)]);
+        assert_eq!(covered_by(assignments(&pairs), nonzero(5)).ranges(), &[Assignment::new(3, 7)]);
+        assert_eq!(covered_by(assignments(&pairs), nonzero(8)).ranges(), &[Assignment::new(6, 6)]);
+        assert!(covered_by(assignments(&pairs), nonzero(9)).is_empty());
+        let apart = [Assignment::new(1, 2), Assignment::new(5, 5)];
+        assert_eq!(covered_by(&apart, nonzero(1)).gaps().ranges(), &[Assignment::new(3, 4)]);
+        let all = IntervalSet::from_iter(assignments(&pairs).copied());
+        assert_eq!(covered_by(assignments(&pairs), NonZeroUsize::MIN), all);
+    }
+
+    #[test]
+    fn d4_example() {
+        assert_eq!(run(Input::from(EXAMPLE).lines()), Ok((2, 4)));
+    }
+
+    #[test]
+    fn d4_empty() {
+        assert_eq!(run(Input::from("").lines()), Ok((0, 0)));
     }
 
     #[test]
-    fn d3_real_1() {
-        let input = file_lines(input_file);
-        assert_eq!(input.into_iter().map(|p| Pair::from_str(&p).expect("parse failed")).filter(|p| p.has_full_containment()).count(), 424);
+    fn d4_malformed_line() {
+        assert_eq!(
+            run(Input::from("2-4,6-8\n\n2-3,4-5").lines()),
+            Err(ErrorKind::MissingDelimiter(',').at(2, ""))
+        );
     }
 
     #[test]
-    fn d3_real_2() {
-        let input = file_lines(input_file);
-        assert_eq!(input.into_iter().map(|p| Pair::from_str(&p).expect("parse failed")).filter(|p| p.has_some_overlap()).count(), 804);
+    fn d4_real() {
+        let Some(input) = real_input(INPUT_FILE) else { return };
+        assert_eq!(run(input.lines()), Ok((424, 804)));
     }
 }
-*/
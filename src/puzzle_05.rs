@@ -1,133 +1,509 @@
-use std::collections::HashSet;
-use std::fs::read_to_string;
-use std::char;
-use std::io::{self, BufRead};
+use std::fmt;
+use std::num::NonZeroUsize;
 use std::str::FromStr;
-use std::collections::hash_map::RandomState;
 
-const input_file_stacks : &'static str = "inputs/puzzle_5_stacks.txt";
-const input_file_moves : &'static str = "inputs/puzzle_5_moves.txt";
+use crate::error::{self, Error, ErrorKind};
+use crate::solution::{Part, Solution};
+use crate::{debug, trace};
 
+pub const INPUT_FILE : &str = "inputs/puzzle_5.txt";
 
-#[derive(Debug)]
-struct State {
-    stacks: Vec<Vec<char>>
+
+/// How a crane carries crates between stacks.
+pub trait Crane {
+    /// Move the top `n` crates of `stacks[source]` onto
+    /// `stacks[destination]`. The stacks exist, are different, and the
+    /// source holds at least `n` crates.
+    fn lift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize);
+
+    /// Undo `lift`: put the top `n` crates of `stacks[destination]` back
+    /// on `stacks[source]` as they were before the lift. The destination
+    /// holds at least `n` crates.
+    fn unlift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize);
+}
+
+/// Move the top `n` crates together, keeping their order.
+fn move_block(stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+    let source_size = stacks[source].len();
+    trace!("source_size: {source_size}, cargo_size: {n}");
+    let mut cargo = stacks[source].split_off(source_size - n);
+    trace!("cargo: {cargo:?}");
+    stacks[destination].append(&mut cargo);
+}
+
+/// Part one's crane, which moves one crate at a time.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+        for _ in 0..n {
+            move_block(stacks, source, destination, 1);
+        }
+    }
+
+    fn unlift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+        self.lift(stacks, destination, source, n);
+    }
+}
+
+/// Part two's crane, which moves all the crates at once.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+        move_block(stacks, source, destination, n);
+    }
+
+    fn unlift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+        move_block(stacks, destination, source, n);
+    }
+}
+
+/// A crane that can only lift so many crates at a time, so it makes a big
+/// move as several smaller ones, top crates first.
+pub struct CappedCrane {
+    capacity: usize,
+}
+
+impl CappedCrane {
+    pub fn new(capacity: NonZeroUsize) -> CappedCrane {
+        CappedCrane { capacity: capacity.get() }
+    }
+}
+
+impl Crane for CappedCrane {
+    fn lift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+        let mut left = n;
+        while left > 0 {
+            let chunk = left.min(self.capacity);
+            move_block(stacks, source, destination, chunk);
+            left -= chunk;
+        }
+    }
+
+    fn unlift(&self, stacks: &mut [Vec<char>], source: usize, destination: usize, n: usize) {
+        // The last, partial chunk landed on top, so it goes back first.
+        let partial = n % self.capacity;
+        if partial > 0 {
+            move_block(stacks, destination, source, partial);
+        }
+        for _ in 0..n / self.capacity {
+            move_block(stacks, destination, source, self.capacity);
+        }
+    }
+}
+
+/// The crane each part of the puzzle uses.
+pub fn crane(part: Part) -> &'static dyn Crane {
+    match part {
+        Part::One => &CrateMover9000,
+        Part::Two => &CrateMover9001,
+    }
+}
+
+/// The stacks of crates at some point in the rearrangement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State {
+    stacks: Vec<Vec<char>>,
+    /// The number under each stack in the drawing, which is how the moves
+    /// refer to it.
+    labels: Vec<usize>,
 }
 
 impl State {
-    pub fn new() -> State {
-        let mut stacks = Vec::new();
-        for _ in 1..10 {
-            let mut stack = Vec::new();
-            // Reserve 100 slots: the maximum height a stack could have
-            // given our starting state.
-            stack.reserve(100);
-            stacks.push(stack);
+    /// The indices of the source and destination stacks, once we know the
+    /// move can be made.
+    fn check(&self, instruction: &Instruction) -> Result<(usize, usize), ErrorKind> {
+        let index = |stack: usize| {
+            self.labels.iter().position(|&l| l == stack).ok_or(ErrorKind::NoSuchStack(stack))
+        };
+        let source_i = index(instruction.source)?;
+        let destination_i = index(instruction.destination)?;
+        let available = self.stacks[source_i].len();
+        let wanted = instruction.n_boxes;
+        if wanted > available {
+            return Err(ErrorKind::NotEnoughCrates { stack: instruction.source, wanted, available });
         }
-        State { stacks }
+        Ok((source_i, destination_i))
+    }
+
+    pub fn stacks(&self) -> &[Vec<char>] {
+        &self.stacks
     }
 
-    pub fn apply_1(&mut self, instruction: &Instruction) {
-        for _ in 0..instruction.n_boxes {
-            println!("{instruction:?}");
-            println!("{self:?}");
-            let cargo = self.stacks[instruction.source - 1].pop().unwrap();
-            self.stacks[instruction.destination - 1].push(cargo);
+    pub fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    fn apply(&mut self, crane: &dyn Crane, instruction: &Instruction) -> Result<(), ErrorKind> {
+        debug!("{instruction:?}");
+        trace!("{self:?}");
+        let (source_i, destination_i) = self.check(instruction)?;
+        if source_i != destination_i {
+            crane.lift(&mut self.stacks, source_i, destination_i, instruction.n_boxes);
+        }
+        Ok(())
+    }
+
+    /// Take back a move `crane` made, so the stacks are as they were before
+    /// it.
+    fn unapply(&mut self, crane: &dyn Crane, instruction: &Instruction) -> Result<(), ErrorKind> {
+        debug!("undo {instruction:?}");
+        trace!("{self:?}");
+        let reversed = Instruction {
+            n_boxes: instruction.n_boxes,
+            source: instruction.destination,
+            destination: instruction.source,
+        };
+        let (destination_i, source_i) = self.check(&reversed)?;
+        if source_i != destination_i {
+            crane.unlift(&mut self.stacks, source_i, destination_i, instruction.n_boxes);
+        }
+        Ok(())
+    }
+
+    /// The crate on top of each stack, or `None` where a stack is empty.
+    pub fn tops(&self) -> Vec<Option<char>> {
+        self.stacks.iter().map(|s| s.last().copied()).collect()
+    }
+}
+
+/// Draw the stacks the way the puzzle does, top row first, with the stack
+/// labels underneath. A crate sits above the first digit of its label.
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let widths: Vec<usize> = self.labels.iter().map(|l| l.to_string().len() + 2).collect();
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let cells: Vec<String> = self.stacks
+                .iter()
+                .zip(&widths)
+                .map(|(s, &w)| {
+                    let cell = s.get(level).map_or(String::new(), |c| format!("[{c}]"));
+                    format!("{cell:w$}")
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" "))?;
         }
+        let labels: Vec<String> = self.labels.iter().map(|l| format!(" {l} ")).collect();
+        write!(f, "{}", labels.join(" "))
     }
+}
+
+/// Read the footer: each stack's label, and the columns it spans.
+fn parse_footer(footer: &str) -> Result<Vec<(usize, usize, usize)>, ErrorKind> {
+    let chars: Vec<char> = footer.chars().collect();
+    let mut labels = Vec::new();
+    let mut column = 0;
+    while column < chars.len() {
+        if chars[column] == ' ' {
+            column += 1;
+            continue;
+        }
+        let start = column;
+        while column < chars.len() && chars[column] != ' ' {
+            column += 1;
+        }
+        let label: String = chars[start..column].iter().collect();
+        let label = error::number(&label)?;
+        if labels.iter().any(|&(seen, _, _)| seen == label) {
+            return Err(ErrorKind::DuplicateLabel(label));
+        }
+        labels.push((label, start, column));
+    }
+    Ok(labels)
+}
 
-    pub fn apply_2(&mut self, instruction: &Instruction) {
-        println!("{self:?}");
-        println!("{instruction:?}");
-        let source_i = instruction.source - 1;
-        let destination_i = instruction.destination - 1;
-        let source_size = self.stacks[source_i].len();
-        let cargo_size = instruction.n_boxes as usize;
-        println!("source_size: {source_size}, cargo_size: {cargo_size}");
-        let mut cargo = self.stacks[source_i].split_off(source_size - cargo_size);
-        println!("cargo: {cargo:?}");
-        self.stacks[destination_i].append(&mut cargo);
+/// Read one row of the drawing: for each stack, the crate at this height
+/// if there is one. A crate, `[X]`, belongs to the stack whose label is
+/// under its letter.
+fn parse_row(row: &str, spans: &[(usize, usize)]) -> Result<Vec<Option<char>>, ErrorKind> {
+    let cells: Vec<char> = row.chars().collect();
+    let cell = |column: usize| cells.get(column).copied().unwrap_or(' ');
+    let unexpected = |column: usize| ErrorKind::UnexpectedChar { column: column + 1, found: cell(column) };
+    let mut crates = vec![None; spans.len()];
+    let mut left = 0;
+    while left < cells.len() {
+        match (cell(left), cell(left + 1), cell(left + 2)) {
+            (' ', _, _) => {
+                left += 1;
+                continue;
+            },
+            ('[', c, ']') if c.is_ascii_alphabetic() => {
+                let stack_i = spans
+                    .iter()
+                    .position(|&(start, end)| (start..end).contains(&(left + 1)))
+                    .filter(|&i| crates[i].is_none())
+                    .ok_or(unexpected(left))?;
+                crates[stack_i] = Some(c);
+            },
+            ('[', _, ']') => return Err(unexpected(left + 1)),
+            ('[', _, _) => return Err(unexpected(left + 2)),
+            _ => return Err(unexpected(left)),
+        }
+        left += 3;
     }
+    Ok(crates)
 }
 
+/// The crate drawing from the puzzle: rows of `[X]` crates, top row first,
+/// above a footer labelling the stacks.
 impl FromStr for State {
-    type Err = String;
+    type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(State {
-            stacks:
-              s
-                .lines()
-                .into_iter()
-                .map(|l| l.chars().into_iter().collect())
-                .collect()
-        })
+        let lines: Vec<&str> = s.lines().collect();
+        let (footer, rows) = lines
+            .split_last()
+            .ok_or_else(|| ErrorKind::MissingField("stack numbers").at(1, s))?;
+        let footer_labels = parse_footer(footer).map_err(|kind| kind.at(lines.len(), footer))?;
+        let spans: Vec<(usize, usize)> = footer_labels.iter().map(|&(_, start, end)| (start, end)).collect();
+        let mut rows_crates = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            rows_crates.push(parse_row(row, &spans).map_err(|kind| kind.at(i + 1, row))?);
+        }
+        // However the crates are moved, no stack can hold more than all of
+        // them.
+        let n_crates = rows_crates.iter().flatten().flatten().count();
+        let mut stacks: Vec<Vec<char>> = spans.iter().map(|_| Vec::with_capacity(n_crates)).collect();
+        for crates in rows_crates.into_iter().rev() {
+            for (stack, c) in stacks.iter_mut().zip(crates) {
+                stack.extend(c);
+            }
+        }
+        let labels = footer_labels.into_iter().map(|(label, _, _)| label).collect();
+        Ok(State { stacks, labels })
     }
 }
 
 impl FromStr for Instruction {
-    type Err = &'static str;
+    type Err = ErrorKind;
+    /// Read exactly `move N from S to D`. Anything else is reported at the
+    /// first character that doesn't fit, or as the field that is missing.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let words : Vec<&str> = s.split(' ').collect();
-        Ok( Instruction {
-            n_boxes: u8::from_str(words[1]).map_err(|_| "Nope")?,
-            source: usize::from_str(words[3]).map_err(|_| "Nope")?,
-            destination: usize::from_str(words[5]).map_err(|_| "Nope")?,
-        } )
+        let chars: Vec<char> = s.chars().collect();
+        let mut column = 0;
+        let mut field = |keyword: &str, name: &'static str| -> Result<usize, ErrorKind> {
+            for expected in keyword.chars() {
+                match chars.get(column) {
+                    None => return Err(ErrorKind::MissingField(name)),
+                    Some(&found) if found != expected => {
+                        return Err(ErrorKind::UnexpectedChar { column: column + 1, found });
+                    },
+                    Some(_) => column += 1,
+                }
+            }
+            let start = column;
+            while column < chars.len() && chars[column] != ' ' {
+                column += 1;
+            }
+            match chars.get(start) {
+                None => Err(ErrorKind::MissingField(name)),
+                Some(&found) if start == column => Err(ErrorKind::UnexpectedChar { column: column + 1, found }),
+                Some(_) => error::number(&chars[start..column].iter().collect::<String>()),
+            }
+        };
+        let instruction = Instruction {
+            n_boxes: field("move ", "crate count")?,
+            source: field(" from ", "source stack")?,
+            destination: field(" to ", "destination stack")?,
+        };
+        // Point at the first extra word, or at the trailing space if
+        // there is nothing else.
+        if column < chars.len() {
+            let extra = chars[column..].iter().position(|&c| c != ' ').map_or(column, |p| column + p);
+            return Err(ErrorKind::UnexpectedChar { column: extra + 1, found: chars[extra] });
+        }
+        Ok(instruction)
     }
 }
 
 #[derive(Clone,Debug)]
 struct Instruction {
-    n_boxes: u8,
+    n_boxes: usize,
     source: usize,
     destination: usize,
 }
 
-#[derive(Debug)]
-struct Problem {
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.n_boxes, self.source, self.destination)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Problem {
     initial_state: State,
     instructions: Vec<Instruction>,
+    /// The input line holding the first move, so a bad move can be
+    /// reported where it was written.
+    first_move_line: usize,
 }
 
 
 
 impl Problem {
-    pub fn load(state_path: &str, moves_path: &str) -> Problem {
-        let initial_state =
-            State::from_str( &read_to_string(state_path).unwrap() ).unwrap();
-        let instructions =
-            read_to_string(moves_path)
-            .unwrap()
-            .lines()
-            .into_iter()
-            .map(|l| Instruction::from_str(l))
-            .collect::<Result<Vec<Instruction>, &'static str>>().unwrap();
-        Problem {initial_state, instructions}
+    /// The tops of the stacks as the puzzle's answer, with `-` standing in
+    /// for an empty stack.
+    fn tops(&self, part: Part) -> Result<String, Error> {
+        let tops = self.solve_with(crane(part))?;
+        Ok(tops.into_iter().map(|c| c.unwrap_or('-')).collect())
     }
 
-    pub fn solve_1(mut self) -> Vec<char> {
-        for i in self.instructions {
-            self.initial_state.apply_1(&i);
+    /// Make every move with `crane`, stopping at the first one that can't
+    /// be made, and calling `each` after each move.
+    fn run<F>(&self, crane: &dyn Crane, mut each: F) -> Result<State, Error>
+      where F: FnMut(usize, &Instruction, &State)
+    {
+        let mut state = self.initial_state.clone();
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            state
+                .apply(crane, instruction)
+                .map_err(|kind| kind.at(self.first_move_line + i, &instruction.to_string()))?;
+            each(i, instruction, &state);
         }
-        self.initial_state.stacks.into_iter().map(|mut s| s.pop().unwrap()).collect()
+        Ok(state)
+    }
+
+    /// The drawing before any moves, then after each move `crane` makes,
+    /// headed by the move.
+    pub fn frames(&self, crane: &dyn Crane) -> Result<Vec<String>, Error> {
+        let n = self.instructions.len();
+        let mut frames = vec![format!("start\n{}", self.initial_state)];
+        self.run(crane, |i, instruction, state| {
+            frames.push(format!("{}/{n}: {instruction}\n{state}", i + 1));
+        })?;
+        Ok(frames)
+    }
+
+    /// The crate on top of each stack once `crane` is done, or `None`
+    /// where a stack ends up empty.
+    pub fn solve_with(&self, crane: &dyn Crane) -> Result<Vec<Option<char>>, Error> {
+        Ok(self.run(crane, |_, _, _| {})?.tops())
+    }
+
+    pub fn initial_state(&self) -> &State {
+        &self.initial_state
     }
 
-    pub fn solve_2(mut self) -> Vec<char> {
-        for i in self.instructions {
-            self.initial_state.apply_2(&i);
+    /// Work backwards from `final_state`, undoing each move `crane` made,
+    /// to the stacks as they must have been before the first move. A move
+    /// that can't be undone is reported at its line.
+    pub fn unsolve_with(&self, crane: &dyn Crane, final_state: &State) -> Result<State, Error> {
+        let mut state = final_state.clone();
+        for (i, instruction) in self.instructions.iter().enumerate().rev() {
+            state
+                .unapply(crane, instruction)
+                .map_err(|kind| kind.at(self.first_move_line + i, &instruction.to_string()))?;
         }
-        self.initial_state.stacks.into_iter().map(|mut s| s.pop().unwrap()).collect()
+        Ok(state)
+    }
+
+    /// The same moves, starting from other stacks.
+    pub fn with_initial_state(&self, initial_state: State) -> Problem {
+        Problem { initial_state, ..self.clone() }
+    }
+
+    /// The crate on top of each stack once the CrateMover 9000 is done.
+    pub fn solve_1(&self) -> Result<Vec<Option<char>>, Error> {
+        self.solve_with(&CrateMover9000)
+    }
+
+    /// The crate on top of each stack once the CrateMover 9001 is done.
+    pub fn solve_2(&self) -> Result<Vec<Option<char>>, Error> {
+        self.solve_with(&CrateMover9001)
+    }
+}
+
+/// The puzzle input as given: the crate drawing, a blank line, then the moves.
+impl FromStr for Problem {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let n_stack_lines = s.lines().take_while(|l| !l.is_empty()).count();
+        let (stacks, moves) = s.split_once("\n\n").ok_or_else(|| {
+            ErrorKind::MissingField("blank line before the moves").at(n_stack_lines + 1, "")
+        })?;
+        let initial_state = State::from_str(stacks)?;
+        let instructions =
+            error::parse_lines(moves.lines().map(String::from))
+            .map_err(|e| Error { line: e.line + n_stack_lines + 1, ..e })?;
+        Ok(Problem {initial_state, instructions, first_move_line: n_stack_lines + 2})
+    }
+}
+
+pub struct Day;
+
+impl Solution for Day {
+    const DAY: u8 = 5;
+    const INPUT_FILE: &'static str = INPUT_FILE;
+    type Input = Problem;
+    type Answer1 = String;
+    type Answer2 = String;
+
+    fn parse(input: &str) -> Result<Problem, Error> {
+        Problem::from_str(input)
+    }
+
+    fn part_1(problem: &Problem) -> Result<String, Error> {
+        problem.tops(Part::One)
+    }
+
+    fn part_2(problem: &Problem) -> Result<String, Error> {
+        problem.tops(Part::Two)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{nonzero, real_input};
+
+    const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    fn load_real() -> Option<Problem> {
+        let input = real_input(INPUT_FILE)?;
+        Some(Problem::from_str(input.text()).unwrap())
+    }
+
+    #[test]
+    fn stacks_follow_the_drawing() {
+        let state = State::from_str("[A]\n 1 ").unwrap();
+        assert_eq!(state.stacks.len(), 1);
+        let drawing = [
+            "                                            [L] ",
+            "[A] [B] [C] [D] [E] [F] [G] [H] [I] [J] [K] [M] ",
+            " 1   2   3   4   5   6   7   8   9   10  11  12 ",
+        ];
+        let state = State::from_str(&drawing.join("\n")).unwrap();
+        assert_eq!(state.stacks.len(), 12);
+        assert_eq!(state.labels, (1..=12).collect::<Vec<usize>>());
+        assert_eq!(state.stacks[11], vec!['M', 'L']);
+        assert!(state.stacks.iter().all(|s| s.capacity() >= 13));
+    }
 
     #[test]
-    fn new_state() {
-        let s = State::new();
-        assert_eq!(s.stacks.len(), 9);
+    fn labels_name_the_stacks() {
+        let problem = Problem::from_str("[A] [B]\n 7   3 \n\nmove 1 from 3 to 7").unwrap();
+        assert_eq!(problem.solve_1().unwrap(), vec![Some('B'), None]);
+        assert_eq!(problem.initial_state.to_string(), "[A] [B]\n 7   3 ");
+        let problem = Problem::from_str("[A] [B]\n 1   2 \n\nmove 1 from 3 to 1").unwrap();
+        assert_eq!(problem.solve_1(), Err(ErrorKind::NoSuchStack(3).at(4, "move 1 from 3 to 1")));
+    }
+
+    #[test]
+    fn draw_multi_digit_labels() {
+        let mut state = State::from_str("[A]\n 1 ").unwrap();
+        state.labels = vec![10];
+        assert_eq!(state.to_string(), "[A] \n 10 ");
+        assert_eq!(State::from_str(&state.to_string()).unwrap().stacks, state.stacks);
     }
 
     #[test]
@@ -139,27 +515,172 @@ mod tests {
         assert_eq!(t, vec![1,3]);
     }
 
+    #[test]
+    fn parse_drawing() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        assert_eq!(problem.initial_state.stacks, vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+        assert_eq!(problem.instructions.len(), 4);
+    }
+
+    #[test]
+    fn parse_drawing_without_trailing_spaces() {
+        let state = State::from_str("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3").unwrap();
+        assert_eq!(state.stacks, vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]);
+    }
+
+    #[test]
+    fn bad_drawing_position() {
+        let err = State::from_str("[N] [C]\n[Z] (M)\n 1   2 ").unwrap_err();
+        assert_eq!(err, ErrorKind::UnexpectedChar { column: 5, found: '(' }.at(2, "[Z] (M)"));
+        let err = State::from_str("[N] [C] [X]\n 1   2 ").unwrap_err();
+        assert_eq!(err, ErrorKind::UnexpectedChar { column: 9, found: '[' }.at(1, "[N] [C] [X]"));
+        let err = State::from_str("[N] [C]\n 1   b ").unwrap_err();
+        assert_eq!(err, ErrorKind::InvalidNumber("b".to_string()).at(2, " 1   b "));
+        assert!(Problem::from_str("[N]\n 1 \nmove 1 from 1 to 1").is_err());
+    }
+
+    #[test]
+    fn bad_instruction_position() {
+        let err = Problem::from_str("[N]\n 1 \n\nmove 1 from 1 to 1\nmove x from 1 to 2").unwrap_err();
+        assert_eq!(err, ErrorKind::InvalidNumber("x".to_string()).at(5, "move x from 1 to 2"));
+        let err = Problem::from_str("[N]\n 1 \n\nmove 1").unwrap_err();
+        assert_eq!(err, ErrorKind::MissingField("source stack").at(4, "move 1"));
+    }
+
+    #[test]
+    fn strict_instructions() {
+        let unexpected = |column, found| Err(ErrorKind::UnexpectedChar { column, found });
+        assert_eq!(Instruction::from_str("jump 1 over 1 and 1").map(|_| ()), unexpected(1, 'j'));
+        assert_eq!(Instruction::from_str("move 1 form 1 to 1").map(|_| ()), unexpected(9, 'o'));
+        assert_eq!(Instruction::from_str("move 1 from 1 to 1 please").map(|_| ()), unexpected(20, 'p'));
+        assert_eq!(Instruction::from_str("move 1 from 1 to 1 ").map(|_| ()), unexpected(19, ' '));
+        assert_eq!(Instruction::from_str("move  1 from 1 to 1").map(|_| ()), unexpected(6, ' '));
+        assert_eq!(Instruction::from_str("move 1 from").map(|_| ()), Err(ErrorKind::MissingField("source stack")));
+        assert_eq!(Instruction::from_str("move 1 from 1 to ").map(|_| ()), Err(ErrorKind::MissingField("destination stack")));
+        assert_eq!(Instruction::from_str("").map(|_| ()), Err(ErrorKind::MissingField("crate count")));
+    }
+
+    #[test]
+    fn duplicate_label() {
+        let err = State::from_str("[A] [B]\n 1   1 ").unwrap_err();
+        assert_eq!(err, ErrorKind::DuplicateLabel(1).at(2, " 1   1 "));
+    }
+
+    #[test]
+    fn draw_state() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        let drawing = problem.initial_state.to_string();
+        assert_eq!(drawing, EXAMPLE.split("\n\n").next().unwrap());
+        assert_eq!(State::from_str(&drawing).unwrap().stacks, problem.initial_state.stacks);
+    }
+
+    #[test]
+    fn frames() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        let frames = problem.frames(&CrateMover9000).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert!(frames[0].starts_with("start\n    [D]    \n"));
+        assert_eq!(frames[2], "2/4: move 3 from 1 to 3
+        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3 ");
+        let frames = problem.frames(&CrateMover9001).unwrap();
+        assert!(frames[4].starts_with("4/4: move 1 from 1 to 2\n        [D]\n"));
+    }
+
+    #[test]
+    fn bad_moves() {
+        let problem = Problem::from_str("[N]\n 1 \n\nmove 1 from 1 to 1\nmove 2 from 1 to 1").unwrap();
+        let err = ErrorKind::NotEnoughCrates { stack: 1, wanted: 2, available: 1 }.at(5, "move 2 from 1 to 1");
+        assert_eq!(problem.solve_1(), Err(err.clone()));
+        assert_eq!(problem.solve_2(), Err(err));
+        let problem = Problem::from_str("[N]\n 1 \n\nmove 1 from 0 to 1").unwrap();
+        assert_eq!(problem.solve_1(), Err(ErrorKind::NoSuchStack(0).at(4, "move 1 from 0 to 1")));
+        let problem = Problem::from_str("[N]\n 1 \n\nmove 1 from 1 to 2").unwrap();
+        assert_eq!(problem.solve_2(), Err(ErrorKind::NoSuchStack(2).at(4, "move 1 from 1 to 2")));
+        assert!(problem.frames(&CrateMover9000).is_err());
+        let problem = Problem::from_str("[N]\n 1   2 \n\nmove 300 from 1 to 2").unwrap();
+        let err = ErrorKind::NotEnoughCrates { stack: 1, wanted: 300, available: 1 }.at(4, "move 300 from 1 to 2");
+        assert_eq!(problem.solve_1(), Err(err));
+    }
+
+    #[test]
+    fn empty_stacks() {
+        let problem = Problem::from_str("[N]    \n[Z] [M]\n 1   2 \n\nmove 1 from 2 to 1").unwrap();
+        assert_eq!(problem.solve_1().unwrap(), vec![Some('M'), None]);
+        assert_eq!(Day::part_2(&problem).unwrap(), "M-");
+    }
+
+    #[test]
+    fn capped_crane() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        assert_eq!(problem.solve_with(&CappedCrane::new(nonzero(1))), problem.solve_1());
+        assert_eq!(problem.solve_with(&CappedCrane::new(nonzero(3))), problem.solve_2());
+        let problem = Problem::from_str("[A]    \n[B]    \n[C]    \n 1   2 \n\nmove 3 from 1 to 2").unwrap();
+        let state = problem.run(&CappedCrane::new(nonzero(2)), |_, _, _| {}).unwrap();
+        assert_eq!(state.stacks[1], vec!['B', 'A', 'C']);
+    }
+
+    #[test]
+    fn move_onto_same_stack() {
+        let problem = Problem::from_str("[A]\n[B]\n 1 \n\nmove 2 from 1 to 1").unwrap();
+        assert_eq!(problem.solve_with(&CappedCrane::new(nonzero(1))).unwrap(), vec![Some('A')]);
+        assert!(problem.solve_2().is_ok());
+    }
+
+    #[test]
+    fn unsolve() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        let cranes: [&dyn Crane; 4] = [&CrateMover9000, &CrateMover9001, &CappedCrane::new(nonzero(2)), &CappedCrane::new(nonzero(3))];
+        for crane in cranes {
+            let final_state = problem.run(crane, |_, _, _| {}).unwrap();
+            assert_eq!(problem.unsolve_with(crane, &final_state).unwrap(), problem.initial_state);
+        }
+    }
+
+    #[test]
+    fn unsolve_from_chosen_message() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        let wanted = State::from_str("        [Z]\n        [N]\n[C]     [D]\n[M] [A] [P]\n 1   2   3 ").unwrap();
+        let start = problem.unsolve_with(&CrateMover9001, &wanted).unwrap();
+        let generated = problem.with_initial_state(start);
+        assert_eq!(generated.solve_2().unwrap(), vec![Some('C'), Some('A'), Some('Z')]);
+        assert_eq!(generated.initial_state().stacks().iter().map(Vec::len).sum::<usize>(), 7);
+    }
+
+    #[test]
+    fn unsolve_impossible() {
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        let final_state = State::from_str("[A] [B] [C]\n 1   2   3 ").unwrap();
+        let err = problem.unsolve_with(&CrateMover9000, &final_state).unwrap_err();
+        assert_eq!(err, ErrorKind::NotEnoughCrates { stack: 3, wanted: 3, available: 1 }.at(7, "move 3 from 1 to 3"));
+    }
+
     #[test]
     fn example_1() {
-        let problem = Problem::load("inputs/puzzle_5_stacks_example.txt", "inputs/puzzle_5_moves_example.txt");
-        assert_eq!(problem.solve_1(), vec!['C', 'M', 'Z']);
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        assert_eq!(problem.solve_1().unwrap(), vec![Some('C'), Some('M'), Some('Z')]);
+        assert_eq!(Day::part_1(&problem).unwrap(), "CMZ");
     }
 
     #[test]
     fn part_1() {
-        let problem = Problem::load("inputs/puzzle_5_stacks.txt", "inputs/puzzle_5_moves.txt");
-        assert_eq!(problem.solve_1(), vec!['Q', 'G', 'T', 'H', 'F', 'Z', 'B', 'H', 'V']);
+        let Some(problem) = load_real() else { return };
+        assert_eq!(Day::part_1(&problem).unwrap(), "QGTHFZBHV");
     }
 
     #[test]
     fn example_2() {
-        let problem = Problem::load("inputs/puzzle_5_stacks_example.txt", "inputs/puzzle_5_moves_example.txt");
-        assert_eq!(problem.solve_2(), vec!['M', 'C', 'D']);
+        let problem = Problem::from_str(EXAMPLE).unwrap();
+        assert_eq!(problem.solve_2().unwrap(), vec![Some('M'), Some('C'), Some('D')]);
+        assert_eq!(Day::part_2(&problem).unwrap(), "MCD");
     }
 
     #[test]
     fn part_2() {
-        let problem = Problem::load("inputs/puzzle_5_stacks.txt", "inputs/puzzle_5_moves.txt");
-        assert_eq!(problem.solve_2(), vec!['M', 'G', 'D', 'M', 'P', 'S', 'Z', 'T', 'M']);
+        let Some(problem) = load_real() else { return };
+        assert_eq!(Day::part_2(&problem).unwrap(), "MGDMPSZTM");
     }
 }
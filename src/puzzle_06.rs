@@ -1,144 +1,267 @@
-use std::fs;
-use std::collections::HashSet;
-use std::collections::hash_map::RandomState;
+use std::io::{self, BufRead};
+use std::num::NonZeroUsize;
 
+use crate::error::{Error, ErrorKind};
+use crate::solution::Solution;
 
 
-const input_file : &'static str = "inputs/puzzle_6.txt";
 
-pub struct CircBuf4 {
-    pub elems: Vec<char>,
-    pub insert_cursor: usize,
+pub const INPUT_FILE : &str = "inputs/puzzle_6.txt";
+
+/// Distinct characters in a start-of-packet marker.
+pub const PACKET_MARKER : NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
+/// Distinct characters in a start-of-message marker.
+pub const MESSAGE_MARKER : NonZeroUsize = NonZeroUsize::new(14).unwrap();
+
+/// The last `width` bytes of a datastream. A count of each byte value is
+/// kept up to date as bytes come and go, so checking for a marker doesn't
+/// rescan the window.
+pub struct Window {
+    width: usize,
+    elems: Vec<u8>,
+    insert_cursor: usize,
+    counts: [u32; 256],
+    distinct: usize,
 }
 
-impl CircBuf4 {
-    pub fn new() -> Self {
-        CircBuf4 {
-            elems: vec![],
-            insert_cursor: 3,
+impl Window {
+    pub fn new(width: NonZeroUsize) -> Self {
+        let width = width.get();
+        Window {
+            width,
+            elems: Vec::with_capacity(width),
+            insert_cursor: width - 1,
+            counts: [0; 256],
+            distinct: 0,
         }
     }
 
-    pub fn push(&mut self, c: char) {
-        if self.elems.len() == 4 {
-            self.insert_cursor = (self.insert_cursor + 1) % 4;
-            self.elems[self.insert_cursor] = c;
+    pub fn push(&mut self, b: u8) {
+        if self.elems.len() == self.width {
+            self.insert_cursor = (self.insert_cursor + 1) % self.width;
+            let evicted = self.elems[self.insert_cursor];
+            self.counts[evicted as usize] -= 1;
+            if self.counts[evicted as usize] == 0 {
+                self.distinct -= 1;
+            }
+            self.elems[self.insert_cursor] = b;
         } else {
-            self.elems.push(c);
+            self.elems.push(b);
+        }
+        self.counts[b as usize] += 1;
+        if self.counts[b as usize] == 1 {
+            self.distinct += 1;
         }
     }
 
+    /// Whether the window is full and holds no repeats.
     pub fn all_unique(&self) -> bool {
-        let hs : HashSet<char, RandomState> = self.elems.iter().cloned().collect();
-        hs.len() == 4
+        self.distinct == self.width
     }
 }
 
-pub struct CircBuf14 {
-    pub elems: Vec<char>,
-    pub insert_cursor: usize,
+/// Line breaks aren't part of a signal: they are skipped and don't count
+/// towards positions, so a signal wrapped over several lines reads as one.
+fn is_line_break(b: u8) -> bool {
+    b == b'\n' || b == b'\r'
 }
 
-impl CircBuf14 {
-    pub fn new() -> Self {
-        CircBuf14 {
-            elems: vec![],
-            insert_cursor: 13,
+/// The number of characters read when the last `width` of them first are
+/// all different, or `None` if that never happens. Positions count bytes,
+/// which are characters for the puzzle's ASCII signals.
+pub fn find_start_sequence(s: &str, width: NonZeroUsize) -> Option<usize> {
+    let mut window = Window::new(width);
+    for (i, b) in s.bytes().filter(|&b| !is_line_break(b)).enumerate() {
+        window.push(b);
+        if window.all_unique() {
+            return Some(i + 1);
         }
     }
+    None
+}
 
-    pub fn push(&mut self, c: char) {
-        if self.elems.len() == 14 {
-            self.insert_cursor = (self.insert_cursor + 1) % 14;
-            self.elems[self.insert_cursor] = c;
-        } else {
-            self.elems.push(c);
+/// Every position at which the last `width` characters are all different,
+/// read a byte at a time so the datastream never has to fit in memory.
+/// Line breaks are skipped, as in `find_start_sequence`.
+pub struct Markers<R> {
+    bytes: io::Bytes<R>,
+    window: Window,
+    position: usize,
+}
+
+impl<R: BufRead> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<io::Result<usize>> {
+        loop {
+            match self.bytes.next()? {
+                Err(e) => return Some(Err(e)),
+                Ok(b) if is_line_break(b) => continue,
+                Ok(b) => {
+                    self.position += 1;
+                    self.window.push(b);
+                    if self.window.all_unique() {
+                        return Some(Ok(self.position));
+                    }
+                }
+            }
         }
     }
+}
 
-    pub fn all_unique(&self) -> bool {
-        let hs : HashSet<char, RandomState> = self.elems.iter().cloned().collect();
-        hs.len() == 14
-    }
+/// Stream the marker positions of `width` characters out of `reader`.
+pub fn markers<R: BufRead>(reader: R, width: NonZeroUsize) -> Markers<R> {
+    Markers { bytes: reader.bytes(), window: Window::new(width), position: 0 }
 }
 
-pub fn find_start_sequence(s: &str) -> u32 {
-    let mut ind = 0;
-    let mut cb = CircBuf4::new();
-    for c in s.chars().into_iter() {
-        cb.push(c);
-        ind += 1;
-        if cb.all_unique() {
-            break;
-        }
-    }
-    ind
+fn find_marker(signal: &str, width: NonZeroUsize) -> Result<usize, Error> {
+    find_start_sequence(signal, width).ok_or_else(|| ErrorKind::NoMarker { width: width.get() }.at(1, signal))
 }
 
-pub fn find_start_sequence_2(s: &str) -> u32 {
-    let mut ind = 0;
-    let mut cb = CircBuf14::new();
-    for c in s.chars().into_iter() {
-        cb.push(c);
-        ind += 1;
-        if cb.all_unique() {
-            break;
-        }
+pub struct Day;
+
+impl Solution for Day {
+    const DAY: u8 = 6;
+    const INPUT_FILE: &'static str = INPUT_FILE;
+    type Input = String;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<String, Error> {
+        Ok(input.trim_end().to_string())
+    }
+
+    fn part_1(signal: &String) -> Result<usize, Error> {
+        find_marker(signal, PACKET_MARKER)
+    }
+
+    fn part_2(signal: &String) -> Result<usize, Error> {
+        find_marker(signal, MESSAGE_MARKER)
     }
-    ind
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Read;
+    use crate::input::{nonzero, real_input};
+
+    #[test]
+    fn window() {
+        let mut w = Window::new(nonzero(4));
+        w.push(b'a');
+        assert_eq!(w.elems, b"a");
+        w.push(b'a');
+        assert_eq!(w.elems, b"aa");
+        w.push(b'c');
+        assert_eq!(w.elems, b"aac");
+        w.push(b'd');
+        assert_eq!(w.elems, b"aacd");
+        assert!(!w.all_unique());
+        w.push(b'e');
+        assert_eq!(w.elems, b"eacd");
+        assert!(w.all_unique());
+        w.push(b'c');
+        assert!(!w.all_unique());
+        w.push(b'g');
+        w.push(b'h');
+        assert_eq!(w.elems, b"ecgh");
+        assert!(w.all_unique());
+    }
 
     #[test]
-    fn circ_buf() {
-        let mut cb = CircBuf4::new();
-        cb.push('a');
-        assert_eq!(cb.elems, vec!['a']);
-        cb.push('a');
-        assert_eq!(cb.elems, vec!['a','a']);
-        cb.push('c');
-        assert_eq!(cb.elems, vec!['a','a','c']);
-        cb.push('d');
-        assert_eq!(cb.elems, vec!['a','a','c','d']);
-        assert!(!cb.all_unique());
-        cb.push('e');
-        assert_eq!(cb.elems, vec!['e','a','c','d']);
-        cb.push('f');
-        cb.push('g');
-        cb.push('h');
-        assert_eq!(cb.elems, vec!['e','f','g','h']);
-        assert!(cb.all_unique());
+    fn window_of_one() {
+        let mut w = Window::new(nonzero(1));
+        assert!(!w.all_unique());
+        w.push(b'a');
+        assert!(w.all_unique());
+        assert_eq!(find_start_sequence("zzz", nonzero(1)), Some(1));
     }
 
     #[test]
     fn example_1() {
-        assert_eq!(find_start_sequence("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), 7);
+        assert_eq!(find_start_sequence("mjqjpqmgbljsphdztnvjfqwrcgsmlb", PACKET_MARKER), Some(7));
+        assert_eq!(find_start_sequence("bvwbjplbgvbhsrlpgdmjqwftvncz", PACKET_MARKER), Some(5));
+        assert_eq!(find_start_sequence("nppdvjthqldpwncqszvftbrmjlhg", PACKET_MARKER), Some(6));
+        assert_eq!(find_start_sequence("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", PACKET_MARKER), Some(10));
+        assert_eq!(find_start_sequence("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", PACKET_MARKER), Some(11));
+    }
+
+    #[test]
+    fn no_marker() {
+        assert_eq!(find_start_sequence("abcabcabc", PACKET_MARKER), None);
+        assert_eq!(find_start_sequence("", PACKET_MARKER), None);
+        assert_eq!(Day::part_1(&"abab".to_string()), Err(ErrorKind::NoMarker { width: 4 }.at(1, "abab")));
+    }
+
+    #[test]
+    fn other_widths() {
+        let signal = "aabcdefghijklmnopqrstuvwxyz";
+        assert_eq!(find_start_sequence(signal, nonzero(26)), Some(27));
+        assert_eq!(find_start_sequence(signal, nonzero(27)), None);
+    }
+
+    #[test]
+    fn streamed_markers() {
+        let found = markers("abcabcd".as_bytes(), nonzero(3)).collect::<io::Result<Vec<usize>>>().unwrap();
+        assert_eq!(found, vec![3, 4, 5, 6, 7]);
+        let found = markers("aab\nc\r\nda".as_bytes(), nonzero(3)).collect::<io::Result<Vec<usize>>>().unwrap();
+        assert_eq!(found, vec![4, 5, 6]);
+        assert_eq!(markers("".as_bytes(), nonzero(4)).count(), 0);
+    }
+
+    #[test]
+    fn streamed_first_marker_matches() {
+        let signal = "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg";
+        let first = markers(io::BufReader::new(signal.as_bytes()), MESSAGE_MARKER).next().unwrap().unwrap();
+        assert_eq!(Some(first), find_start_sequence(signal, MESSAGE_MARKER));
+    }
+
+    #[test]
+    fn line_breaks_agree() {
+        let signal = "ab\nca\r\nbdxy\n";
+        for width in 1..=5 {
+            let first = markers(signal.as_bytes(), nonzero(width)).next().transpose().unwrap();
+            assert_eq!(first, find_start_sequence(signal, nonzero(width)), "width {width}");
+        }
+        assert_eq!(find_start_sequence(signal, nonzero(3)), Some(3));
+        assert_eq!(find_start_sequence(signal, nonzero(4)), Some(6));
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("unplugged"))
+        }
+    }
+
+    #[test]
+    fn streamed_read_error() {
+        let mut found = markers(io::BufReader::new(Broken), PACKET_MARKER);
+        assert_eq!(found.next().unwrap().unwrap_err().to_string(), "unplugged");
     }
 
     #[test]
     fn part_1() {
-        use std::fs::read_to_string;
-        let input = read_to_string(input_file).unwrap();
-        assert_eq!(find_start_sequence(&input), 1480);
+        let Some(input) = real_input(INPUT_FILE) else { return };
+        assert_eq!(find_start_sequence(input.text(), PACKET_MARKER), Some(1480));
     }
 
     #[test]
     fn examples_2() {
-        assert_eq!(find_start_sequence_2("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), 19);
-        assert_eq!(find_start_sequence_2("bvwbjplbgvbhsrlpgdmjqwftvncz"), 23);
-        assert_eq!(find_start_sequence_2("nppdvjthqldpwncqszvftbrmjlhg"), 23);
-        assert_eq!(find_start_sequence_2("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
-        assert_eq!(find_start_sequence_2("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
+        assert_eq!(find_start_sequence("mjqjpqmgbljsphdztnvjfqwrcgsmlb", MESSAGE_MARKER), Some(19));
+        assert_eq!(find_start_sequence("bvwbjplbgvbhsrlpgdmjqwftvncz", MESSAGE_MARKER), Some(23));
+        assert_eq!(find_start_sequence("nppdvjthqldpwncqszvftbrmjlhg", MESSAGE_MARKER), Some(23));
+        assert_eq!(find_start_sequence("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", MESSAGE_MARKER), Some(29));
+        assert_eq!(find_start_sequence("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", MESSAGE_MARKER), Some(26));
     }
 
     #[test]
     fn part_2() {
-        use std::fs::read_to_string;
-        let input = read_to_string(input_file).unwrap();
-        assert_eq!(find_start_sequence_2(&input), 2746);
+        let Some(input) = real_input(INPUT_FILE) else { return };
+        assert_eq!(find_start_sequence(input.text(), MESSAGE_MARKER), Some(2746));
     }
 
 }
@@ -0,0 +1,131 @@
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use crate::error::{Error, ErrorKind};
+use crate::{puzzle_01, puzzle_02, puzzle_03, puzzle_04, puzzle_05, puzzle_06};
+
+/// Which half of a day's puzzle to solve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl FromStr for Part {
+    type Err = ErrorKind;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            _ => Err(ErrorKind::InvalidPart(s.to_string())),
+        }
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::One => f.pad("1"),
+            Part::Two => f.pad("2"),
+        }
+    }
+}
+
+/// The shape every day's puzzle takes: parse the input once, then answer
+/// either part from the parsed form.
+pub trait Solution {
+    /// The day of December this puzzle belongs to.
+    const DAY: u8;
+
+    /// The file read when no other input is given.
+    const INPUT_FILE: &'static str;
+
+    type Input;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    fn parse(input: &str) -> Result<Self::Input, Error>;
+    fn part_1(input: &Self::Input) -> Result<Self::Answer1, Error>;
+    fn part_2(input: &Self::Input) -> Result<Self::Answer2, Error>;
+}
+
+/// A `Solution` with its types erased, so every day can sit in one registry.
+pub trait Puzzle {
+    fn day(&self) -> u8;
+    fn input_file(&self) -> &'static str;
+    fn parse(&self, input: &str) -> Result<Box<dyn Parsed>, Error>;
+}
+
+/// A day's parsed input, ready to answer either part.
+pub trait Parsed {
+    fn solve(&self, part: Part) -> Result<String, Error>;
+}
+
+struct Registered<S>(PhantomData<S>);
+
+struct ParsedInput<S: Solution>(S::Input);
+
+impl<S: Solution + 'static> Puzzle for Registered<S> {
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn input_file(&self) -> &'static str {
+        S::INPUT_FILE
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Parsed>, Error> {
+        Ok(Box::new(ParsedInput::<S>(S::parse(input)?)))
+    }
+}
+
+impl<S: Solution> Parsed for ParsedInput<S> {
+    fn solve(&self, part: Part) -> Result<String, Error> {
+        match part {
+            Part::One => S::part_1(&self.0).map(|a| a.to_string()),
+            Part::Two => S::part_2(&self.0).map(|a| a.to_string()),
+        }
+    }
+}
+
+fn register<S: Solution + 'static>() -> Box<dyn Puzzle> {
+    Box::new(Registered::<S>(PhantomData))
+}
+
+/// Every solved day, in order.
+pub fn registry() -> Vec<Box<dyn Puzzle>> {
+    vec![
+        register::<puzzle_01::Day>(),
+        register::<puzzle_02::Day>(),
+        register::<puzzle_03::Day>(),
+        register::<puzzle_04::Day>(),
+        register::<puzzle_05::Day>(),
+        register::<puzzle_06::Day>(),
+    ]
+}
+
+/// Look up the puzzle for `day`.
+pub fn find(day: u8) -> Option<Box<dyn Puzzle>> {
+    registry().into_iter().find(|p| p.day() == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_is_keyed_by_day() {
+        let days: Vec<u8> = registry().iter().map(|p| p.day()).collect();
+        assert_eq!(days, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(find(4).map(|p| p.day()), Some(4));
+        assert!(find(25).is_none());
+    }
+
+    #[test]
+    fn solve_through_registry() {
+        let day_6 = find(6).unwrap().parse("mjqjpqmgbljsphdztnvjfqwrcgsmlb\n").unwrap();
+        assert_eq!(day_6.solve(Part::One), Ok("7".to_string()));
+        assert_eq!(day_6.solve(Part::Two), Ok("19".to_string()));
+    }
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Set to 1 (or `debug`) or 2 (or `trace`) to turn on diagnostics without
+/// passing `-v`.
+pub const ENV_VAR : &str = "AOC_VERBOSE";
+
+/// How much the puzzles say about what they're doing, on stderr.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Just the answers.
+    Off,
+    /// A line per step, such as each crane move.
+    Debug,
+    /// Everything, such as the state after every input line.
+    Trace,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> Level {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => Level::Off,
+        1 => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && level <= self::level()
+}
+
+/// The level for a count of `-v` flags.
+pub fn from_count(count: usize) -> Level {
+    match count {
+        0 => Level::Off,
+        1 => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+/// The level asked for by the environment, if it names one.
+pub fn from_env() -> Option<Level> {
+    match env::var(ENV_VAR).ok()?.to_ascii_lowercase().as_str() {
+        "0" | "off" => Some(Level::Off),
+        "1" | "debug" => Some(Level::Debug),
+        "2" | "trace" => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+/// Print to stderr when running with `-v` or more.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::Level::Debug) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Print to stderr when running with `-vv`.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::Level::Trace) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert_eq!(from_count(0), Level::Off);
+        assert_eq!(from_count(1), Level::Debug);
+        assert_eq!(from_count(3), Level::Trace);
+        assert!(Level::Debug < Level::Trace);
+    }
+
+    #[test]
+    fn off_by_default() {
+        assert!(!enabled(Level::Debug));
+        assert!(!enabled(Level::Trace));
+        assert!(!enabled(Level::Off));
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use crate::error::{self, Error, ErrorKind};
+use crate::input::Input;
+use crate::solution::{Part, Puzzle};
+
+/// Where the known answers live by default.
+pub const ANSWERS_FILE : &str = "answers.txt";
+
+/// The answers we know to be right, by day and part.
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers {
+    known: HashMap<(u8, Part), String>,
+}
+
+impl Answers {
+    pub fn get(&self, day: u8, part: Part) -> Option<&str> {
+        self.known.get(&(day, part)).map(|a| a.as_str())
+    }
+}
+
+fn parse_answer(line: &str) -> Result<(u8, Part, String), ErrorKind> {
+    let mut fields = line.split_whitespace();
+    let mut field = |name| fields.next().ok_or(ErrorKind::MissingField(name));
+    let day = error::number(field("day")?)?;
+    let part = Part::from_str(field("part")?)?;
+    let answer = field("answer")?.to_string();
+    Ok((day, part, answer))
+}
+
+/// One answer per line, as `<day> <part> <answer>`. Blank lines and lines
+/// starting with `#` are ignored.
+impl FromStr for Answers {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut known = HashMap::new();
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (day, part, answer) = parse_answer(line).map_err(|kind| kind.at(i + 1, line))?;
+            known.insert((day, part), answer);
+        }
+        Ok(Answers { known })
+    }
+}
+
+/// How a puzzle's answer compares with the known one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail { expected: String },
+    /// Solved, but there's no known answer to compare with.
+    Unknown,
+    /// The input file isn't there, so nothing was solved.
+    Skipped,
+    /// The puzzle rejected its input.
+    Error(String),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Status::Pass => "pass",
+            Status::Fail { .. } => "FAIL",
+            Status::Unknown => "unknown",
+            Status::Skipped => "skipped",
+            Status::Error(_) => "ERROR",
+        };
+        f.pad(name)
+    }
+}
+
+/// The outcome for one part of one day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub day: u8,
+    pub part: Part,
+    pub answer: Option<String>,
+    pub status: Status,
+}
+
+/// Solve both parts of every puzzle, reading each input with `load`, and
+/// compare against the known answers.
+pub fn verify<F>(puzzles: &[Box<dyn Puzzle>], answers: &Answers, load: F) -> Vec<Check>
+  where F: Fn(&dyn Puzzle) -> io::Result<Input>
+{
+    let mut checks = Vec::new();
+    for puzzle in puzzles {
+        let day = puzzle.day();
+        let parsed = load(puzzle.as_ref())
+            .map_err(|e| if e.kind() == io::ErrorKind::NotFound { Status::Skipped } else { Status::Error(e.to_string()) })
+            .and_then(|input| puzzle.parse(input.text()).map_err(|e| Status::Error(e.to_string())));
+        for part in [Part::One, Part::Two] {
+            let solved = parsed
+                .as_ref()
+                .map_err(|status| status.clone())
+                .and_then(|p| p.solve(part).map_err(|e| Status::Error(e.to_string())));
+            let check = match solved {
+                Err(status) => Check { day, part, answer: None, status },
+                Ok(answer) => {
+                    let status = match answers.get(day, part) {
+                        None => Status::Unknown,
+                        Some(expected) if expected == answer => Status::Pass,
+                        Some(expected) => Status::Fail { expected: expected.to_string() },
+                    };
+                    Check { day, part, answer: Some(answer), status }
+                }
+            };
+            checks.push(check);
+        }
+    }
+    checks
+}
+
+/// Lay the checks out as a table, one row per part.
+pub fn table(checks: &[Check]) -> String {
+    let mut out = format!("{:>3} {:>4}  {:<7}  {:<16}  {}\n", "day", "part", "status", "answer", "expected");
+    for check in checks {
+        let answer = check.answer.as_deref().unwrap_or("-");
+        let note = match &check.status {
+            Status::Fail { expected } => expected.as_str(),
+            Status::Error(e) => e.as_str(),
+            _ => "",
+        };
+        let row = format!("{:>3} {:>4}  {:<7}  {:<16}  {}", check.day, check.part, check.status, answer, note);
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    #[test]
+    fn parse_answers() {
+        let answers = Answers::from_str("# comment\n\n1 1 67027\n5 2 MGDMPSZTM\n").unwrap();
+        assert_eq!(answers.get(1, Part::One), Some("67027"));
+        assert_eq!(answers.get(5, Part::Two), Some("MGDMPSZTM"));
+        assert_eq!(answers.get(1, Part::Two), None);
+    }
+
+    #[test]
+    fn parse_bad_answers() {
+        assert_eq!(Answers::from_str("1 3 12"), Err(ErrorKind::InvalidPart("3".to_string()).at(1, "1 3 12")));
+        assert_eq!(Answers::from_str("\n1 1"), Err(ErrorKind::MissingField("answer").at(2, "1 1")));
+        assert_eq!(Answers::from_str("x 1 2"), Err(ErrorKind::InvalidNumber("x".to_string()).at(1, "x 1 2")));
+    }
+
+    #[test]
+    fn answers_file_parses() {
+        let answers = Answers::from_str(include_str!("../answers.txt")).unwrap();
+        assert_eq!(answers.get(6, Part::Two), Some("2746"));
+    }
+
+    #[test]
+    fn verify_statuses() {
+        let puzzles = vec![solution::find(6).unwrap(), solution::find(4).unwrap(), solution::find(1).unwrap()];
+        let answers = Answers::from_str("6 1 7\n6 2 20\n4 1 2").unwrap();
+        let checks = verify(&puzzles, &answers, |p| match p.day() {
+            6 => Ok(Input::from("mjqjpqmgbljsphdztnvjfqwrcgsmlb")),
+            4 => Ok(Input::from("2-4,6-8\nnope")),
+            _ => Input::from_path("inputs/no_such_puzzle.txt"),
+        });
+        let statuses: Vec<Status> = checks.iter().map(|c| c.status.clone()).collect();
+        assert_eq!(statuses[0], Status::Pass);
+        assert_eq!(statuses[1], Status::Fail { expected: "20".to_string() });
+        assert!(matches!(statuses[2], Status::Error(_)));
+        assert_eq!(statuses[4], Status::Skipped);
+        assert_eq!(checks[1].answer.as_deref(), Some("19"));
+
+        let table = table(&checks);
+        assert!(table.contains("  6    2  FAIL     19                20\n"));
+        assert!(table.contains("  1    1  skipped  -\n"));
+    }
+
+    #[test]
+    fn unknown_answer() {
+        let puzzles = vec![solution::find(6).unwrap()];
+        let checks = verify(&puzzles, &Answers::default(), |_| Ok(Input::from("abcd")));
+        assert_eq!(checks[0].status, Status::Unknown);
+        assert!(matches!(checks[1].status, Status::Error(_)));
+    }
+}